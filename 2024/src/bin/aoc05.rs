@@ -1,3 +1,4 @@
+use aoc2024::dot::Graph;
 use std::{
     cmp::Ordering,
     collections::HashMap,
@@ -36,6 +37,11 @@ fn main() -> std::io::Result<()> {
         p2 += update.0[i] as u64;
     }
     dbg!(p2);
+    // e.g. `--emit-dot aoc05.dot`
+    if let Some(i) = args.iter().position(|a| a == "--emit-dot") {
+        let path = args.get(i + 1).expect("a file to write the DOT to");
+        std::fs::write(path, generate_graphviz(&adj).to_string())?;
+    }
     Ok(())
 }
 
@@ -105,6 +111,22 @@ fn to_adjacency_list(orderings: &[PageOrdering]) -> HashMap<u8, Vec<u8>> {
     adj
 }
 
+/// Edges point from earlier pages to later ones.
+fn generate_graphviz(adj: &HashMap<u8, Vec<u8>>) -> Graph {
+    let mut gv = Graph::digraph("aoc05");
+    let mut pages: Vec<_> = adj.keys().copied().collect();
+    pages.sort_unstable();
+    for page in pages {
+        gv.node(page);
+        let mut later = adj[&page].clone();
+        later.sort_unstable();
+        for to in later {
+            gv.edge(page, to);
+        }
+    }
+    gv
+}
+
 fn shuffle_valid(adj: &HashMap<u8, Vec<u8>>, update: &mut PageUpdate) {
     // println!("update {update:?}");
     update.0.sort_by(|a, b| {
@@ -1,3 +1,7 @@
+use aoc2024::{
+    checked::{narrow, Checked, Overflow},
+    digits::Digits,
+};
 use std::{
     fmt::Debug,
     fs::File,
@@ -10,15 +14,20 @@ fn main() -> std::io::Result<()> {
     let f = File::open(path_input)?;
     // Part one
     let eqs = parse_input(BufReader::new(f));
-    let p1: u64 = eqs
+    let p1 = eqs
         .iter()
-        .filter_map(|eq| eq.could_be_true().then_some(eq.test_value))
-        .sum();
+        .filter(|eq| eq.could_be_true().unwrap_or_else(|e| panic!("{eq:?}: {e}")))
+        .try_fold(0_u128, |sum, eq| sum.try_add(eq.test_value))
+        .expect("sum fits in 128 bits");
     dbg!(p1);
-    let p2: u64 = eqs
+    let p2 = eqs
         .iter()
-        .filter_map(|eq| eq.could_be_true_with_concat().then_some(eq.test_value))
-        .sum();
+        .filter(|eq| {
+            eq.could_be_true_with_concat()
+                .unwrap_or_else(|e| panic!("{eq:?}: {e}"))
+        })
+        .try_fold(0_u128, |sum, eq| sum.try_add(eq.test_value))
+        .expect("sum fits in 128 bits");
     dbg!(p2);
     Ok(())
 }
@@ -33,7 +42,7 @@ fn parse_input(r: BufReader<File>) -> Vec<Equation> {
 #[derive(Debug)]
 struct Equation {
     /// Input's largest value is about 10^14.5, and there are under 1000 of them.
-    test_value: u64,
+    test_value: u128,
     /// Input's longest list has twelve numbers.
     other_values: Vec<u64>,
 }
@@ -44,7 +53,7 @@ impl From<&str> for Equation {
         let test_value = value
             .get(0..i)
             .expect("sane input")
-            .parse::<u64>()
+            .parse::<u128>()
             .expect("sane input");
         let other_values: Vec<_> = value
             .get(i + 2..)
@@ -60,83 +69,96 @@ impl From<&str> for Equation {
     }
 }
 
+/// Every solver tries `u64` first, and `u128` if an accumulator overflowed
+/// that; only if both overflow is there an error.
+type Solver<T> = fn(T, T, &[u64]) -> Result<bool, Overflow>;
+
 impl Equation {
-    pub fn could_be_true(&self) -> bool {
-        Self::_could_be_true(
-            self.test_value,
-            self.other_values[0],
+    pub fn could_be_true(&self) -> Result<bool, Overflow> {
+        self.solve::<u64>(Self::_could_be_true)
+            .or_else(|Overflow| self.solve::<u128>(Self::_could_be_true))
+    }
+
+    fn solve<T: From<u64> + TryFrom<u128>>(&self, solver: Solver<T>) -> Result<bool, Overflow> {
+        solver(
+            narrow(self.test_value)?,
+            self.other_values[0].into(),
             &self.other_values[1..],
         )
     }
-    fn _could_be_true(target: u64, accumulator: u64, unused: &[u64]) -> bool {
-        if let Some((head, tail)) = unused.split_first() {
+
+    fn _could_be_true<T: Checked + From<u64> + PartialEq>(
+        target: T,
+        accumulator: T,
+        unused: &[u64],
+    ) -> Result<bool, Overflow> {
+        if let Some((&head, tail)) = unused.split_first() {
             // Known operators: addition, multiplication
-            let add = Self::_could_be_true(target, accumulator + head, tail);
+            let add = Self::_could_be_true(target, accumulator.try_add(head.into())?, tail)?;
             if add {
-                return true;
+                return Ok(true);
             }
-            let mul = Self::_could_be_true(target, accumulator * head, tail);
-            mul
+            let mul = Self::_could_be_true(target, accumulator.try_mul(head.into())?, tail)?;
+            Ok(mul)
         } else {
-            target == accumulator
+            Ok(target == accumulator)
         }
     }
 
-    pub fn could_be_true_with_concat(&self) -> bool {
-        Self::_could_be_true_with_concat(
-            self.test_value,
-            self.other_values[0],
-            &self.other_values[1..],
-        )
+    pub fn could_be_true_with_concat(&self) -> Result<bool, Overflow> {
+        self.solve::<u64>(Self::_could_be_true_with_concat)
+            .or_else(|Overflow| self.solve::<u128>(Self::_could_be_true_with_concat))
     }
 
-    fn _could_be_true_with_concat(target: u64, accumulator: u64, unused: &[u64]) -> bool {
+    fn _could_be_true_with_concat<T: Checked + Digits + From<u64> + PartialEq>(
+        target: T,
+        accumulator: T,
+        unused: &[u64],
+    ) -> Result<bool, Overflow> {
         if let Some((&head, tail)) = unused.split_first() {
             // Known operators: addition, multiplication, concatenation
-            let add = Self::_could_be_true_with_concat(target, accumulator + head, tail);
+            let add =
+                Self::_could_be_true_with_concat(target, accumulator.try_add(head.into())?, tail)?;
             if add {
-                return true;
+                return Ok(true);
             }
-            let mul = Self::_could_be_true_with_concat(target, accumulator * head, tail);
+            let mul =
+                Self::_could_be_true_with_concat(target, accumulator.try_mul(head.into())?, tail)?;
             if mul {
-                return true;
+                return Ok(true);
             }
-            let head_width = Self::width_as_power_of_ten(head);
-            let concat =
-                Self::_could_be_true_with_concat(target, accumulator * head_width + head, tail);
-            concat
+            let concat = accumulator.concat(head.into())?;
+            Self::_could_be_true_with_concat(target, concat, tail)
         } else {
-            target == accumulator
+            Ok(target == accumulator)
         }
     }
-
-    fn width_as_power_of_ten(x: u64) -> u64 {
-        10_u64.pow((x as f64 + 0.1).log10().ceil() as u32)
-    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     #[test]
-    fn one() {
-        assert_eq!(10, Equation::width_as_power_of_ten(1));
-    }
-    #[test]
-    fn ten() {
-        assert_eq!(100, Equation::width_as_power_of_ten(10));
-    }
-    #[test]
-    fn hundred() {
-        assert_eq!(1000, Equation::width_as_power_of_ten(100));
-    }
-    #[test]
-    fn not_round_1() {
-        assert_eq!(10, Equation::width_as_power_of_ten(2));
+    fn concat() {
+        // 10^14 and up used to come out a digit short
+        for head in [1, 10, 100, 2, 11, 99, 100000000000000, u64::MAX] {
+            let eq: Equation = format!("7{head}: 7 {head}").as_str().into();
+            assert_eq!(Ok(false), eq.could_be_true(), "{head}");
+            assert_eq!(Ok(true), eq.could_be_true_with_concat(), "{head}");
+        }
     }
+
     #[test]
-    fn not_round_2() {
-        assert_eq!(100, Equation::width_as_power_of_ten(11));
-        assert_eq!(100, Equation::width_as_power_of_ten(99));
+    fn wide() {
+        let eq: Equation = "100000000000000000000: 10000000000 10000000000".into();
+        assert_eq!(Ok(true), eq.could_be_true());
+        let eq: Equation = "1000000000000000000000: 10000000000 10000000000".into();
+        assert_eq!(Ok(false), eq.could_be_true());
+        assert_eq!(Ok(false), eq.could_be_true_with_concat());
+        let eq: Equation = "18446744073709551615: 1844674407370955161 5".into();
+        assert_eq!(Ok(true), eq.could_be_true_with_concat());
+        let eq: Equation =
+            "1: 18446744073709551615 18446744073709551615 18446744073709551615".into();
+        assert_eq!(Err(Overflow), eq.could_be_true());
     }
 }
@@ -1,6 +1,11 @@
+use aoc2024::{
+    checked::{Checked, Overflow},
+    digits::Digits,
+};
 use std::{
     collections::HashMap,
     fs::File,
+    hash::Hash,
     io::{BufReader, Read},
 };
 fn main() -> std::io::Result<()> {
@@ -12,7 +17,7 @@ fn main() -> std::io::Result<()> {
     let p1 = do_part_one(&stones);
     dbg!(p1);
     // Part two
-    let p2 = do_part_two(&stones);
+    let p2 = do_part_two(&stones).unwrap_or_else(|e| panic!("{e}"));
     dbg!(p2);
     Ok(())
 }
@@ -118,43 +123,144 @@ mod tests {
     fn m202411() {
         assert_eq!("22264", multiply_strings("11", "2024"));
     }
+
+    #[test]
+    fn wide_stones() {
+        let stones = |s: &str| s.split(' ').map(str::to_owned).collect::<Vec<_>>();
+        let small = stones("125 17");
+        assert_eq!(Ok(65601038650482), do_part_two_as::<u64>(&small));
+        assert_eq!(Ok(65601038650482), do_part_two_as::<String>(&small));
+        // 19 digits, so the first blink multiplies past u64::MAX
+        let big = stones("9999999999999999999 17");
+        assert_eq!(Err(StoneError::Overflow), do_part_two_as::<u64>(&big));
+        let want = do_part_two_as::<String>(&big);
+        assert_eq!(want, do_part_two_as::<u128>(&big));
+        assert_eq!(want, do_part_two(&big));
+        let huge = stones("1234567890123456789012345678901234567890123");
+        assert_eq!(Err(StoneError::Overflow), do_part_two_as::<u128>(&huge));
+        assert_eq!(do_part_two_as::<String>(&huge), do_part_two(&huge));
+        // not worth retrying wider
+        for bad in ["-1", "+1", "1e3", "x"] {
+            let want = Err(StoneError::NotANumber(bad.to_owned()));
+            assert_eq!(want, do_part_two_as::<u64>(&stones(&format!("125 {bad}"))));
+            assert_eq!(want, do_part_two(&stones(bad)));
+        }
+    }
+}
+
+/// Tries `u64` stones, then `u128`, then decimal strings, which cannot
+/// overflow.  Counts are `u128`: each blink at most doubles them.
+fn do_part_two(stones: &[String]) -> Result<u128, StoneError> {
+    let or_wider = |result, wider: fn(&[String]) -> Result<u128, StoneError>| match result {
+        Err(StoneError::Overflow) => wider(stones),
+        result => result,
+    };
+    let result = do_part_two_as::<u64>(stones);
+    let result = or_wider(result, do_part_two_as::<u128>);
+    or_wider(result, do_part_two_as::<String>)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum StoneError {
+    /// Not a stone at all, however wide the representation.
+    NotANumber(String),
+    /// A stone or the count did not fit; a wider representation might.
+    Overflow,
+}
+
+impl From<Overflow> for StoneError {
+    fn from(_: Overflow) -> Self {
+        StoneError::Overflow
+    }
+}
+
+impl std::fmt::Display for StoneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoneError::NotANumber(s) => write!(f, "not a number: `{s}`"),
+            StoneError::Overflow => write!(f, "{Overflow}"),
+        }
+    }
+}
+
+/// Engravings are plain decimal numbers, without any sign.
+fn check_engraving(s: &str) -> Result<(), StoneError> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(StoneError::NotANumber(s.to_owned()));
+    }
+    Ok(())
 }
 
-fn do_part_two(stones: &[String]) -> u64 {
+fn do_part_two_as<T: Engraving>(stones: &[String]) -> Result<u128, StoneError> {
     let mut cache = HashMap::new();
-    let mut retval = 0;
+    let mut retval = 0_u128;
     for st in stones {
-        let st = st.parse::<u64>().unwrap();
-        retval += do_part_two_rec(&mut cache, 0, st);
+        let st = T::parse(st)?;
+        retval = retval.try_add(do_part_two_rec(&mut cache, 0, st)?)?;
     }
-    retval
+    Ok(retval)
 }
 
 const PART_TWO_LIMIT: u64 = 75;
-fn do_part_two_rec(cache: &mut HashMap<(u64, u64), u64>, iteration: u64, count: u64) -> u64 {
-    let cache_key = (iteration, count);
+fn do_part_two_rec<T: Engraving>(
+    cache: &mut HashMap<(u64, T), u128>,
+    iteration: u64,
+    stone: T,
+) -> Result<u128, Overflow> {
+    if iteration >= PART_TWO_LIMIT {
+        return Ok(1);
+    }
+    let cache_key = (iteration, stone);
     if let Some(&retval) = cache.get(&cache_key) {
-        return retval;
+        return Ok(retval);
+    }
+    let mut retval = 0_u128;
+    for st in cache_key.1.blink()?.into_iter().flatten() {
+        retval = retval.try_add(do_part_two_rec(cache, iteration + 1, st)?)?;
     }
-    if iteration >= PART_TWO_LIMIT {
-        return 1;
-    }
-    if count == 0 {
-        return do_part_two_rec(cache, iteration + 1, 1);
-    }
-    let w = width(count);
-    let retval = if w % 2 == 0 {
-        let places = 10_u64.pow(w / 2);
-        let a = do_part_two_rec(cache, iteration + 1, count / places);
-        let b = do_part_two_rec(cache, iteration + 1, count % places);
-        a + b
-    } else {
-        do_part_two_rec(cache, iteration + 1, count * 2024)
-    };
     cache.insert(cache_key, retval);
-    retval
+    Ok(retval)
+}
+
+/// The number on a stone, in whichever representation is wide enough.
+trait Engraving: Sized + Clone + Eq + Hash {
+    fn parse(s: &str) -> Result<Self, StoneError>;
+    /// What the stone turns into; see [`apply_rule`].
+    fn blink(&self) -> Result<[Option<Self>; 2], Overflow>;
+}
+
+impl Engraving for String {
+    fn parse(s: &str) -> Result<Self, StoneError> {
+        check_engraving(s)?;
+        Ok(s.to_owned())
+    }
+
+    fn blink(&self) -> Result<[Option<Self>; 2], Overflow> {
+        Ok(apply_rule(self))
+    }
 }
 
-fn width(x: u64) -> u32 {
-    (x as f64 + 0.1).log10().ceil() as u32
+macro_rules! impl_engraving {
+    ($($t:ty),*) => {$(
+        impl Engraving for $t {
+            fn parse(s: &str) -> Result<Self, StoneError> {
+                check_engraving(s)?;
+                // only digits, so too many of them is all that can go wrong
+                s.parse().map_err(|_| StoneError::Overflow)
+            }
+
+            fn blink(&self) -> Result<[Option<Self>; 2], Overflow> {
+                let x = *self;
+                if x == 0 {
+                    return Ok([Some(1), None]);
+                }
+                if let Some((a, b)) = x.split_half() {
+                    return Ok([Some(a), Some(b)]);
+                }
+                Ok([Some(x.try_mul(2024)?), None])
+            }
+        }
+    )*};
 }
+
+impl_engraving!(u64, u128);
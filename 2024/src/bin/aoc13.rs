@@ -1,8 +1,10 @@
+use aoc2024::checked::{self, Checked, Overflow};
 use core::str;
 use regex::Regex;
 use std::{
     fs::File,
-    io::{BufReader, Read},
+    io::{BufRead, BufReader},
+    ops::RangeInclusive,
     str::FromStr,
     sync::LazyLock,
 };
@@ -10,49 +12,57 @@ fn main() -> std::io::Result<()> {
     let args: Vec<String> = std::env::args().collect();
     let path_input = args.get(1).expect("Should have an input file");
     let f = File::open(path_input)?;
-    let mut claws = parse_input(BufReader::new(f));
-    // dbg!(&claws);
+    let mut machines = parse_input(BufReader::new(f));
+    // dbg!(&machines);
     // Part one
-    let mut p1 = 0;
-    for claw in claws.iter() {
-        // dbg!(claw);
-        if let Some(tokens) = claw.cheapest_path() {
-            p1 += tokens;
+    // Each machine's tokens fit in an i128, but two near 2^126 overflow it.
+    let mut p1 = 0_i128;
+    for machine in machines.iter() {
+        // dbg!(machine);
+        let tokens = machine.cheapest_path(Some(100));
+        if let Some(tokens) = tokens.unwrap_or_else(|e| panic!("{machine:?}: {e}")) {
+            p1 = p1
+                .try_add(tokens)
+                .unwrap_or_else(|e| panic!("part one: {e}"));
         }
     }
     dbg!(p1);
     // Part two
-    let mut p2 = 0;
+    let mut p2 = 0_i128;
     const OOPS: i64 = 10000000000000;
-    for claw in claws.iter_mut() {
-        claw.prize.0 += OOPS;
-        claw.prize.1 += OOPS;
-        if let Some(tokens) = claw.cheapest_path() {
-            p2 += tokens;
+    for machine in machines.iter_mut() {
+        let prize = (machine.prize.0.try_add(OOPS), machine.prize.1.try_add(OOPS));
+        machine.prize = (prize.0.expect("prize fits"), prize.1.expect("prize fits"));
+        let tokens = machine.cheapest_path(None);
+        if let Some(tokens) = tokens.unwrap_or_else(|e| panic!("{machine:?}: {e}")) {
+            p2 = p2
+                .try_add(tokens)
+                .unwrap_or_else(|e| panic!("part two: {e}"));
         }
     }
     dbg!(p2);
     Ok(())
 }
 
-fn parse_input(r: BufReader<File>) -> Vec<ClawMachine> {
-    let mut claws = vec![];
+fn parse_input(r: impl BufRead) -> Vec<Machine> {
+    let mut machines = vec![];
     let mut buf = vec![];
     for b in r.bytes().chain(std::iter::once(Ok(b'\n'))) {
         let b = b.expect("sane input");
         if b == b'\n' && buf.last().filter(|x| **x == b'\n').is_some() {
-            let claw = str::from_utf8(&buf)
+            let machine = str::from_utf8(&buf)
                 .expect("ASCII")
-                .parse::<ClawMachine>()
+                .parse::<Machine>()
                 .expect("sane input");
-            claws.push(claw);
+            machines.push(machine);
             buf.clear();
         }
         buf.push(b);
     }
-    claws
+    machines
 }
 
+/// The puzzle's machine: exactly two buttons, solved in closed form.
 #[derive(Debug)]
 struct ClawMachine {
     button_a: (i64, i64),
@@ -62,62 +72,802 @@ struct ClawMachine {
     cost_b: i64,
 }
 
+/// Any number of buttons, each with its own cost.
+#[derive(Debug, Clone)]
+struct Machine {
+    buttons: Vec<Button>,
+    prize: (i64, i64),
+}
+
+#[derive(Debug, Clone)]
+struct Button {
+    label: char,
+    step: (i64, i64),
+    cost: i64,
+}
+
+/// What was wrong, such as which number did not fit.
 #[derive(Debug, PartialEq, Eq)]
-struct ClawMachineParseError;
+struct ClawMachineParseError(String);
+
+/// `field` is for the error, as in `button A X`.
+fn parse_number(field: &str, s: &str) -> Result<i64, ClawMachineParseError> {
+    s.parse()
+        .map_err(|e| ClawMachineParseError(format!("{field} `{s}`: {e}")))
+}
 
-static REGEX_PARSE_BUTTON: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^Button [AB]: X\+(\d+), Y\+(\d+)$").unwrap());
+#[derive(Debug, PartialEq, Eq)]
+enum SolveError {
+    /// No press limit was given, and besides the two buttons solved in
+    /// closed form there is this one, which could be pressed arbitrarily
+    /// often.
+    Unbounded(char),
+    /// Some press count, token count or intermediate value did not fit.
+    Overflow,
+}
+
+impl From<Overflow> for SolveError {
+    fn from(_: Overflow) -> Self {
+        SolveError::Overflow
+    }
+}
+
+impl std::fmt::Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::Unbounded(label) => {
+                write!(f, "button {label} could be pressed without limit")
+            }
+            SolveError::Overflow => write!(f, "{Overflow}"),
+        }
+    }
+}
+
+/// Buttons other than A (3 tokens) and B (1 token) must state their cost,
+/// as in `Button C: X+5, Y-2, Cost=4`.
+static REGEX_PARSE_BUTTON: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^Button ([A-Z]): X([+-]\d+), Y([+-]\d+)(?:, Cost=(\d+))?$").unwrap()
+});
 static REGEX_PARSE_PRIZE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^Prize: X=(\d+), Y=(\d+)$").unwrap());
-impl FromStr for ClawMachine {
+    LazyLock::new(|| Regex::new(r"^Prize: X=(-?\d+), Y=(-?\d+)$").unwrap());
+impl FromStr for Machine {
     type Err = ClawMachineParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.trim().lines();
-        let a = lines.next().ok_or(ClawMachineParseError)?;
-        let a_xy = REGEX_PARSE_BUTTON
-            .captures_iter(a)
-            .map(|cap| cap.extract())
-            .map(|(_, [x, y])| (x.parse::<i64>().unwrap(), y.parse::<i64>().unwrap()))
-            .next()
-            .ok_or(ClawMachineParseError)?;
-        let b = lines.next().ok_or(ClawMachineParseError)?;
-        let b_xy = REGEX_PARSE_BUTTON
-            .captures_iter(b)
-            .map(|cap| cap.extract())
-            .map(|(_, [x, y])| (x.parse::<i64>().unwrap(), y.parse::<i64>().unwrap()))
-            .next()
-            .ok_or(ClawMachineParseError)?;
-        let p = lines.next().ok_or(ClawMachineParseError)?;
-        let prize = REGEX_PARSE_PRIZE
-            .captures_iter(p)
-            .map(|cap| cap.extract())
-            .map(|(_, [x, y])| (x.parse::<i64>().unwrap(), y.parse::<i64>().unwrap()))
-            .next()
-            .ok_or(ClawMachineParseError)?;
-        Ok(ClawMachine {
-            button_a: a_xy,
-            button_b: b_xy,
+        let mut lines: Vec<&str> = s.trim().lines().collect();
+        let p = lines
+            .pop()
+            .ok_or_else(|| ClawMachineParseError("no prize".to_owned()))?;
+        let (_, [x, y]) = REGEX_PARSE_PRIZE
+            .captures(p)
+            .ok_or_else(|| ClawMachineParseError(format!("not a prize: `{p}`")))?
+            .extract();
+        let prize = (parse_number("prize X", x)?, parse_number("prize Y", y)?);
+        let mut buttons = vec![];
+        for line in lines {
+            let cap = REGEX_PARSE_BUTTON
+                .captures(line)
+                .ok_or_else(|| ClawMachineParseError(format!("not a button: `{line}`")))?;
+            let label = cap[1].chars().next().unwrap();
+            let field = |name| format!("button {label} {name}");
+            let step = (
+                parse_number(&field("X"), &cap[2])?,
+                parse_number(&field("Y"), &cap[3])?,
+            );
+            let cost = match (cap.get(4), label) {
+                (Some(cost), _) => parse_number(&field("cost"), cost.as_str())?,
+                (None, 'A') => 3,
+                (None, 'B') => 1,
+                (None, _) => {
+                    return Err(ClawMachineParseError(format!(
+                        "button {label} needs a cost"
+                    )))
+                }
+            };
+            buttons.push(Button { label, step, cost });
+        }
+        Ok(Machine { buttons, prize })
+    }
+}
+
+impl Machine {
+    /// Fewest tokens to win the prize, pressing each button at most
+    /// `max_presses` times if given.  Costs must not be negative.
+    fn cheapest_path(&self, max_presses: Option<i64>) -> Result<Option<i128>, SolveError> {
+        Ok(self
+            .cheapest_presses(max_presses)?
+            .map(|(tokens, _)| tokens))
+    }
+
+    /// The tokens for [`Self::cheapest_path`], and how often to press each
+    /// button.  Branch and bound over the press counts of all buttons but
+    /// two, which are left to [`ClawMachine::cheapest_presses`].
+    fn cheapest_presses(
+        &self,
+        max_presses: Option<i64>,
+    ) -> Result<Option<(i128, Vec<i64>)>, SolveError> {
+        let limits: Vec<_> = (0..self.buttons.len())
+            .map(|i| self.press_bound(i, max_presses))
+            .collect();
+        // leave unbounded buttons (if any) for the closed form
+        let mut order: Vec<usize> = (0..self.buttons.len()).collect();
+        order.sort_by_key(|&i| limits[i].is_none());
+        let branched = order.len().saturating_sub(2);
+        if let Some(&i) = order[..branched].iter().find(|&&i| limits[i].is_none()) {
+            return Err(SolveError::Unbounded(self.buttons[i].label));
+        }
+        let mut search = BranchAndBound {
+            machine: self,
+            limits: limits.into_iter().map(|l| l.unwrap_or(0)).collect(),
+            order,
+            max_presses,
+            presses: vec![0; self.buttons.len()],
+            best: None,
+        };
+        search.branch(0, self.prize, 0)?;
+        Ok(search.best)
+    }
+
+    /// Most presses button `i` could need: the limit, or, when every button
+    /// moves the same way as the prize along some axis, the prize's distance.
+    fn press_bound(&self, i: usize, max_presses: Option<i64>) -> Option<i64> {
+        let mut bound = max_presses;
+        // in i128, since negating i64::MIN overflows
+        for axis in [|v: (i64, i64)| v.0 as i128, |v: (i64, i64)| v.1 as i128] {
+            for sign in [1, -1] {
+                let step = sign * axis(self.buttons[i].step);
+                let prize = sign * axis(self.prize);
+                if step > 0 && prize >= 0 && self.buttons.iter().all(|b| sign * axis(b.step) >= 0) {
+                    let most = i64::try_from(prize / step).unwrap_or(i64::MAX);
+                    bound = Some(bound.map_or(most, |b| b.min(most)));
+                }
+            }
+        }
+        bound
+    }
+}
+
+struct BranchAndBound<'a> {
+    machine: &'a Machine,
+    limits: Vec<i64>,
+    order: Vec<usize>,
+    max_presses: Option<i64>,
+    presses: Vec<i64>,
+    best: Option<(i128, Vec<i64>)>,
+}
+
+impl BranchAndBound<'_> {
+    fn branch(
+        &mut self,
+        depth: usize,
+        remaining: (i64, i64),
+        tokens: i128,
+    ) -> Result<(), Overflow> {
+        let buttons = &self.machine.buttons;
+        let branched = self.order.len().saturating_sub(2);
+        if depth == branched {
+            return self.leaf(remaining, tokens);
+        }
+        let rest = self.order[depth..].iter().map(|&i| &buttons[i]);
+        let bound = match relaxed_cost(rest, remaining) {
+            Ok(None) => return Ok(()),
+            Ok(Some(bound)) => Some(bound),
+            // too big to compare, so search on without pruning
+            Err(Overflow) => None,
+        };
+        if let (Some(bound), Some((best, _))) = (bound, &self.best) {
+            if cannot_beat(tokens, bound, *best).unwrap_or(false) {
+                return Ok(());
+            }
+        }
+        let i = self.order[depth];
+        let Button { step, cost, .. } = buttons[i];
+        for n in self.presses_to_try(depth, remaining)?.into_iter().flatten() {
+            let tokens = tokens.try_add(i128::from(n) * i128::from(cost))?;
+            if self.best.as_ref().is_some_and(|(best, _)| tokens >= *best) {
+                break;
+            }
+            self.presses[i] = n;
+            let remaining = (
+                remaining.0.try_sub(n.try_mul(step.0)?)?,
+                remaining.1.try_sub(n.try_mul(step.1)?)?,
+            );
+            self.branch(depth + 1, remaining, tokens)?;
+        }
+        self.presses[i] = 0;
+        Ok(())
+    }
+
+    /// Press counts worth trying for the button branched on at `depth`, in
+    /// increasing order: all up to its limit, except for the last button
+    /// branched on.  The two buttons after it then have at most one way to
+    /// finish, with press and token counts linear in its presses, and whether
+    /// they come out whole repeats every `|det|` presses: so the cheapest is
+    /// within one such period of either end of the range where they are in
+    /// bounds.
+    fn presses_to_try(
+        &self,
+        depth: usize,
+        remaining: (i64, i64),
+    ) -> Result<[RangeInclusive<i64>; 2], Overflow> {
+        let buttons = &self.machine.buttons;
+        let i = self.order[depth];
+        let nothing = RangeInclusive::new(1, 0);
+        let everything = [0..=self.limits[i], nothing.clone()];
+        let [a, b] = self.order[depth + 1..] else {
+            return Ok(everything);
+        };
+        let (step_a, step_b) = (buttons[a].step, buttons[b].step);
+        let det = determinant(step_a, step_b);
+        if det == 0 {
+            return Ok(everything);
+        }
+        // Cramer's rule after `n` presses, scaled by `det`'s sign:
+        // `|det| a = c0 + c1 n`, and the same for `b`
+        let step = buttons[i].step;
+        let sign = det.signum();
+        let mut range = Some((0, i128::from(self.limits[i])));
+        for (c0, c1) in [
+            (determinant(remaining, step_b), -determinant(step, step_b)),
+            (determinant(step_a, remaining), -determinant(step_a, step)),
+        ] {
+            let (c0, c1) = (sign * c0, sign * c1);
+            range = range.and_then(|r| narrow(r, c0, c1));
+            if let Some(limit) = self.max_presses {
+                let c0 = i128::from(limit).try_mul(det.abs())?.try_sub(c0)?;
+                range = range.and_then(|r| narrow(r, c0, -c1));
+            }
+        }
+        let Some((lo, hi)) = range else {
+            return Ok([nothing.clone(), nothing]);
+        };
+        let period = det.abs();
+        let first_end = hi.min(lo.saturating_add(period - 1));
+        let last_start = (first_end + 1).max(hi.saturating_sub(period - 1));
+        // all within the limit, which is an `i64`
+        let (lo, hi) = (lo as i64, hi as i64);
+        let (first_end, last_start) = (first_end as i64, last_start as i64);
+        Ok([lo..=first_end, last_start..=hi])
+    }
+
+    fn leaf(&mut self, remaining: (i64, i64), tokens: i128) -> Result<(), Overflow> {
+        let buttons = &self.machine.buttons;
+        let rest = &self.order[self.order.len().saturating_sub(2)..];
+        // a missing button never moves and costs nothing
+        let button = |k: usize| {
+            rest.get(k)
+                .map_or(((0, 0), 0), |&i| (buttons[i].step, buttons[i].cost))
+        };
+        let ((button_a, cost_a), (button_b, cost_b)) = (button(0), button(1));
+        let claw = ClawMachine {
+            button_a,
+            button_b,
+            prize: remaining,
+            cost_a,
+            cost_b,
+        };
+        let Some((a, b)) = claw.cheapest_presses(self.max_presses)? else {
+            return Ok(());
+        };
+        let tokens = tokens.try_add(claw.tokens((a, b)))?;
+        if self.best.as_ref().is_some_and(|(best, _)| *best <= tokens) {
+            return Ok(());
+        }
+        let mut presses = self.presses.clone();
+        for (&i, n) in rest.iter().zip([a, b]) {
+            presses[i] = n;
+        }
+        self.best = Some((tokens, presses));
+        Ok(())
+    }
+}
+
+/// Fewest tokens to reach `prize` if buttons could be pressed fractionally
+/// often, as `(numerator, denominator)` with the denominator positive, or
+/// `None` if even that cannot.  This linear program has an optimum using at
+/// most two buttons, so try every one and every pair.  Exact, since a bound
+/// that rounds up could prune the best plan; `Overflow` if some candidate is
+/// too big to compare.
+fn relaxed_cost<'a>(
+    buttons: impl Iterator<Item = &'a Button> + Clone,
+    prize: (i64, i64),
+) -> Result<Option<(i128, i128)>, Overflow> {
+    if prize == (0, 0) {
+        return Ok(Some((0, 1)));
+    }
+    let mut best: Option<(i128, i128)> = None;
+    let mut consider = |num: i128, den: i128| -> Result<(), Overflow> {
+        let (num, den) = if den < 0 { (-num, -den) } else { (num, den) };
+        if let Some((best_num, best_den)) = best {
+            if num.try_mul(best_den)? >= best_num.try_mul(den)? {
+                return Ok(());
+            }
+        }
+        best = Some((num, den));
+        Ok(())
+    };
+    for (i, b1) in buttons.clone().enumerate() {
+        let cost1 = i128::from(b1.cost);
+        // along the prize's own line, some positive multiple of it
+        if b1.step != (0, 0) && determinant(b1.step, prize) == 0 {
+            let (along, step) = if b1.step.0 != 0 {
+                (prize.0, b1.step.0)
+            } else {
+                (prize.1, b1.step.1)
+            };
+            if along.signum() == step.signum() {
+                consider(cost1 * i128::from(along), i128::from(step))?;
+            }
+        }
+        for b2 in buttons.clone().skip(i + 1) {
+            // Cramer's rule, with every determinant scaled to a positive `det`
+            let det = determinant(b1.step, b2.step);
+            let sign = det.signum();
+            let u = sign * determinant(prize, b2.step);
+            let v = sign * determinant(b1.step, prize);
+            if det != 0 && u >= 0 && v >= 0 {
+                let tokens = cost1.try_mul(u)?.try_add(i128::from(b2.cost).try_mul(v)?)?;
+                consider(tokens, sign * det)?;
+            }
+        }
+    }
+    Ok(best)
+}
+
+/// Whether `tokens` plus the relaxed cost `num / den` is at least `best`.
+fn cannot_beat(tokens: i128, (num, den): (i128, i128), best: i128) -> Result<bool, Overflow> {
+    Ok(tokens.try_mul(den)?.try_add(num)? >= best.try_mul(den)?)
+}
+
+impl ClawMachine {
+    /// Exact, since with neither costs nor presses negative each product is
+    /// below 2^126.
+    fn tokens(&self, (a, b): (i64, i64)) -> i128 {
+        i128::from(self.cost_a) * i128::from(a) + i128::from(self.cost_b) * i128::from(b)
+    }
+
+    /// Cheapest way to win the prize, as how often to press A and B, pressing
+    /// each at most `max_presses` times if given.  Costs must not be negative.
+    /// Works in `i128`, where no determinant of `i64` vectors overflows.
+    fn cheapest_presses(&self, max_presses: Option<i64>) -> Result<Option<(i64, i64)>, Overflow> {
+        let det = determinant(self.button_a, self.button_b);
+        if det == 0 {
+            return self.cheapest_presses_collinear(max_presses);
+        }
+        // Cramer's rule: the only solution, if it is whole and not negative.
+        let a = determinant(self.prize, self.button_b);
+        let b = determinant(self.button_a, self.prize);
+        if a % det != 0 || b % det != 0 {
+            return Ok(None);
+        }
+        let (a, b) = (a / det, b / det);
+        let limit = max_presses.map_or(i128::MAX, i128::from);
+        if !(0..=limit).contains(&a) || !(0..=limit).contains(&b) {
+            return Ok(None);
+        }
+        Ok(Some((checked::narrow(a)?, checked::narrow(b)?)))
+    }
+
+    /// Both buttons move along the same line, so the prize must lie on it
+    /// too, and then there may be many ways to get there: along the line's
+    /// smallest whole step `d`, with `A = alpha d`, `B = beta d` and
+    /// `prize = pi d`, solve `alpha a + beta b = pi` and pick the cheapest.
+    fn cheapest_presses_collinear(
+        &self,
+        max_presses: Option<i64>,
+    ) -> Result<Option<(i64, i64)>, Overflow> {
+        let wide = |v: (i64, i64)| (i128::from(v.0), i128::from(v.1));
+        let direction = [self.button_a, self.button_b]
+            .into_iter()
+            .find(|&v| v != (0, 0));
+        let Some((dx, dy)) = direction.map(wide) else {
+            // neither button moves the claw
+            return Ok((self.prize == (0, 0)).then_some((0, 0)));
+        };
+        let g = gcd(dx, dy);
+        let d = (dx / g, dy / g);
+        let along = |v: (i64, i64)| {
+            let v = wide(v);
+            if d.0 * v.1 != d.1 * v.0 {
+                None
+            } else if d.0 != 0 {
+                Some(v.0 / d.0)
+            } else {
+                Some(v.1 / d.1)
+            }
+        };
+        let alpha = along(self.button_a).expect("same line");
+        let beta = along(self.button_b).expect("same line");
+        let Some(pi) = along(self.prize) else {
+            return Ok(None);
+        };
+        let (g, x, y) = extended_gcd(alpha, beta);
+        if pi % g != 0 {
+            return Ok(None);
+        }
+        // a = a0 + step_a t, b = b0 + step_b t for any whole t
+        let (a0, b0) = (x.try_mul(pi / g)?, y.try_mul(pi / g)?);
+        let (step_a, step_b) = (beta / g, -alpha / g);
+        let mut t_range = (i128::MIN, i128::MAX);
+        for (c0, c1) in [(a0, step_a), (b0, step_b)] {
+            let Some(range) = narrow(t_range, c0, c1) else {
+                return Ok(None);
+            };
+            t_range = range;
+            if let Some(limit) = max_presses {
+                let Some(range) = narrow(t_range, i128::from(limit) - c0, -c1) else {
+                    return Ok(None);
+                };
+                t_range = range;
+            }
+        }
+        let slope = i128::from(self.cost_a)
+            .try_mul(step_a)?
+            .try_add(i128::from(self.cost_b).try_mul(step_b)?)?;
+        let t = match slope.signum() {
+            1 => t_range.0,
+            -1 => t_range.1,
+            _ => t_range.0.max(t_range.1.min(0)),
+        };
+        debug_assert!(t != i128::MIN && t != i128::MAX, "costs are bounded below");
+        let a = a0.try_add(step_a.try_mul(t)?)?;
+        let b = b0.try_add(step_b.try_mul(t)?)?;
+        Ok(Some((checked::narrow(a)?, checked::narrow(b)?)))
+    }
+}
+
+/// Exact: each product is at most 2^126 in size, so their difference fits.
+fn determinant(v0: (i64, i64), v1: (i64, i64)) -> i128 {
+    (v0.0 as i128 * v1.1 as i128) - (v0.1 as i128 * v1.0 as i128)
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    extended_gcd(a, b).0
+}
+
+/// `(g, x, y)` with `a x + b y = g = gcd(a, b)`, and `g >= 0`.  Only for
+/// values that fit in an `i64`, which keeps every step in range.
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// Narrows the whole numbers `t` in `range` to those with `c0 + c1 t >= 0`,
+/// or `None` if there are none left.
+fn narrow(range: (i128, i128), c0: i128, c1: i128) -> Option<(i128, i128)> {
+    let (mut lo, mut hi) = range;
+    match c1.signum() {
+        1 => lo = lo.max((-c0).div_euclid(c1) + ((-c0).rem_euclid(c1) != 0) as i128),
+        -1 => hi = hi.min(c0.div_euclid(-c1)),
+        _ if c0 < 0 => return None,
+        _ => {}
+    }
+    (lo <= hi).then_some((lo, hi))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc2024::rng::XorShift64;
+
+    fn cheapest(claw: &ClawMachine, max_presses: Option<i64>) -> Option<i128> {
+        let presses = claw.cheapest_presses(max_presses).unwrap();
+        presses.map(|p| claw.tokens(p))
+    }
+
+    fn claw(a: (i64, i64), b: (i64, i64), prize: (i64, i64)) -> ClawMachine {
+        ClawMachine {
+            button_a: a,
+            button_b: b,
             prize,
             cost_a: 3,
             cost_b: 1,
-        })
+        }
     }
-}
 
-impl ClawMachine {
-    fn cheapest_path(&self) -> Option<i64> {
-        // FIXME Check for linearity and numerical instabilities.
-        fn determinate(v0: (i64, i64), v1: (i64, i64)) -> i64 {
-            (v0.0 * v1.1) - (v0.1 * v1.0)
-        }
-        let det = determinate(self.button_a, self.button_b);
-        let a = determinate(self.prize, self.button_b);
-        let b = determinate(self.button_a, self.prize);
-        if a % det == 0 && b % det == 0 {
-            Some(self.cost_a * (a / det) + self.cost_b * (b / det))
-        } else {
-            None
+    #[test]
+    fn example() {
+        let claws = [
+            claw((94, 34), (22, 67), (8400, 5400)),
+            claw((26, 66), (67, 21), (12748, 12176)),
+            claw((17, 86), (84, 37), (7870, 6450)),
+            claw((69, 23), (27, 71), (18641, 10279)),
+        ];
+        let tokens: Vec<_> = claws.iter().map(|c| cheapest(c, Some(100))).collect();
+        assert_eq!(vec![Some(280), None, Some(200), None], tokens);
+    }
+
+    const EXAMPLE: &str = "Button A: X+94, Y+34
+Button B: X+22, Y+67
+Prize: X=8400, Y=5400
+
+Button A: X+26, Y+66
+Button B: X+67, Y+21
+Prize: X=12748, Y=12176
+
+Button A: X+17, Y+86
+Button B: X+84, Y+37
+Prize: X=7870, Y=6450
+
+Button A: X+69, Y+23
+Button B: X+27, Y+71
+Prize: X=18641, Y=10279
+";
+
+    #[test]
+    fn more_buttons() {
+        let machines = parse_input(EXAMPLE.as_bytes());
+        let tokens: Vec<_> = machines
+            .iter()
+            .map(|m| m.cheapest_path(Some(100)).unwrap())
+            .collect();
+        assert_eq!(vec![Some(280), None, Some(200), None], tokens);
+
+        let mut m = machines[0].clone();
+        let c: Machine = "Button C: X+116, Y+101, Cost=3\nPrize: X=0, Y=0"
+            .parse()
+            .unwrap();
+        m.buttons.extend(c.buttons);
+        assert_eq!(Ok(Some((240, vec![40, 0, 40]))), m.cheapest_presses(None));
+        m.buttons[2].cost = 5;
+        assert_eq!(Ok(Some((280, vec![80, 40, 0]))), m.cheapest_presses(None));
+        m.buttons.push(m.buttons[2].clone());
+        m.prize = (-10, 5);
+        assert_eq!(Ok(None), m.cheapest_presses(None));
+        m.buttons[3].step = (-1, 0);
+        assert_eq!(Ok(None), m.cheapest_presses(None));
+        m.buttons[3].step = (-1, -1);
+        assert_eq!(Err(SolveError::Unbounded('A')), m.cheapest_presses(None));
+        assert_eq!(Ok(None), m.cheapest_path(Some(100)));
+
+        assert!("Button C: X+1, Y+1\nPrize: X=0, Y=0"
+            .parse::<Machine>()
+            .is_err());
+        assert!("Button A: X+1, Y+1".parse::<Machine>().is_err());
+        // too big for an i64, which is all any of them may be
+        let too_big = "99999999999999999999";
+        let err = |what: &str| {
+            let e = too_big.parse::<i64>().unwrap_err();
+            Err(ClawMachineParseError(format!("{what}: {e}")))
+        };
+        let m = format!("Button A: X+1, Y+{too_big}\nPrize: X=0, Y=0");
+        assert_eq!(
+            err(&format!("button A Y `+{too_big}`")),
+            m.parse::<Machine>().map(|_| ())
+        );
+        let m = format!("Button C: X+1, Y+1, Cost={too_big}\nPrize: X=0, Y=0");
+        assert_eq!(
+            err(&format!("button C cost `{too_big}`")),
+            m.parse::<Machine>().map(|_| ())
+        );
+        let m = format!("Button A: X+1, Y+1\nPrize: X=0, Y={too_big}");
+        assert_eq!(
+            err(&format!("prize Y `{too_big}`")),
+            m.parse::<Machine>().map(|_| ())
+        );
+    }
+
+    #[test]
+    fn degenerate() {
+        assert_eq!(Some(7), cheapest(&claw((2, 2), (3, 3), (7, 7)), None));
+        assert_eq!(Some(4), cheapest(&claw((2, 2), (3, 3), (12, 12)), None));
+        assert_eq!(Some(11), cheapest(&claw((2, 2), (3, 3), (12, 12)), Some(3)));
+        assert_eq!(None, cheapest(&claw((2, 2), (3, 3), (12, 13)), None));
+        assert_eq!(None, cheapest(&claw((2, 2), (4, 4), (7, 7)), None));
+        assert_eq!(None, cheapest(&claw((1, 0), (0, 1), (-1, 5)), None));
+        assert_eq!(None, cheapest(&claw((1, 0), (0, 1), (101, 5)), Some(100)));
+        assert_eq!(Some(0), cheapest(&claw((0, 0), (0, 0), (0, 0)), None));
+        assert_eq!(Some(5), cheapest(&claw((0, 0), (0, 1), (0, 5)), None));
+    }
+
+    #[test]
+    fn wide_values() {
+        // determinant 2^80 - 1
+        let big = 1 << 40;
+        let c = claw((big, 1), (1, big), (3 * big + 5, 3 + 5 * big));
+        assert_eq!(Ok(Some((3, 5))), c.cheapest_presses(None));
+        let c = claw((i64::MIN, 0), (i64::MIN / 2, 0), (i64::MIN, 0));
+        assert_eq!(Ok(Some((0, 2))), c.cheapest_presses(None));
+        let c = claw((-1, 0), (1, 0), (i64::MIN, 0));
+        // 2^63 presses of A
+        assert_eq!(Ok(None), c.cheapest_presses(Some(i64::MAX)));
+        assert_eq!(Err(Overflow), c.cheapest_presses(None));
+        let c = claw((1, 0), (0, 1), (i64::MAX, i64::MAX));
+        assert_eq!(Ok(Some((i64::MAX, i64::MAX))), c.cheapest_presses(None));
+        // tokens past an i64 just widen
+        let tokens = 4 * i128::from(i64::MAX);
+        assert_eq!(tokens, c.tokens((i64::MAX, i64::MAX)));
+        let m = Machine {
+            buttons: vec![
+                Button {
+                    label: 'A',
+                    step: (1, 0),
+                    cost: 3,
+                },
+                Button {
+                    label: 'B',
+                    step: (0, 1),
+                    cost: 1,
+                },
+            ],
+            prize: c.prize,
+        };
+        assert_eq!(Ok(Some(tokens)), m.cheapest_path(None));
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = XorShift64::new(0x13);
+        for _ in 0..2000 {
+            let a = (rng.range(-2..7), rng.range(-2..7));
+            // often parallel to A
+            let b = match rng.below(3) {
+                0 => (a.0 * rng.range(-1..4), a.1 * rng.range(-1..4)),
+                _ => (rng.range(-2..7), rng.range(-2..7)),
+            };
+            let prize = (rng.range(-5..55), rng.range(-5..55));
+            let mut c = claw(a, b, prize);
+            (c.cost_a, c.cost_b) = (rng.range(0..5), rng.range(0..5));
+            let limit = 20;
+            let mut want = None;
+            for pa in 0..=limit {
+                for pb in 0..=limit {
+                    let at = (a.0 * pa + b.0 * pb, a.1 * pa + b.1 * pb);
+                    if at == prize {
+                        let tokens = c.cost_a * pa + c.cost_b * pb;
+                        want = Some(want.map_or(tokens, |w: i64| w.min(tokens)));
+                    }
+                }
+            }
+            assert_eq!(want.map(i128::from), cheapest(&c, Some(limit)), "{c:?}");
+        }
+    }
+
+    #[test]
+    fn branch_and_bound_matches_brute_force() {
+        let mut rng = XorShift64::new(0x48);
+        let limit = 12;
+        for _ in 0..500 {
+            let buttons: Vec<Button> = ['A', 'B', 'C']
+                .into_iter()
+                .map(|label| Button {
+                    label,
+                    step: (rng.range(-2..7), rng.range(-2..7)),
+                    cost: rng.range(0..5),
+                })
+                .collect();
+            let m = Machine {
+                prize: (rng.range(-5..55), rng.range(-5..55)),
+                buttons,
+            };
+            let want = brute_force(&m, limit);
+            let got = m.cheapest_presses(Some(limit)).unwrap();
+            let got_tokens = got.as_ref().map(|(tokens, _)| *tokens);
+            assert_eq!(want, got_tokens, "{m:?}");
+            if let Some((tokens, presses)) = got {
+                assert!(presses.iter().all(|n| (0..=limit).contains(n)));
+                let at = m.buttons.iter().zip(&presses).fold((0, 0), |at, (b, n)| {
+                    (at.0 + n * b.step.0, at.1 + n * b.step.1)
+                });
+                assert_eq!(m.prize, at);
+                assert_eq!(
+                    tokens,
+                    m.buttons
+                        .iter()
+                        .zip(&presses)
+                        .map(|(b, n)| i128::from(n * b.cost))
+                        .sum()
+                );
+            }
+        }
+    }
+
+    /// Fewest tokens over every way of pressing each button up to `limit`
+    /// times.
+    fn brute_force(m: &Machine, limit: i64) -> Option<i128> {
+        let mut presses = vec![0; m.buttons.len()];
+        let mut want = None;
+        loop {
+            let at = m.buttons.iter().zip(&presses).fold((0, 0), |at, (b, n)| {
+                (at.0 + n * b.step.0, at.1 + n * b.step.1)
+            });
+            if at == m.prize {
+                let tokens: i128 = m
+                    .buttons
+                    .iter()
+                    .zip(&presses)
+                    .map(|(b, n)| i128::from(n * b.cost))
+                    .sum();
+                want = Some(want.map_or(tokens, |w: i128| w.min(tokens)));
+            }
+            // next combination, like counting in base `limit + 1`
+            let Some(i) = presses.iter().position(|&n| n < limit) else {
+                return want;
+            };
+            presses[i] += 1;
+            presses[..i].fill(0);
+        }
+    }
+
+    #[test]
+    fn branch_and_bound_large_coordinates() {
+        // Nearly parallel steps around 10^12, where a relaxed bound computed
+        // in floating point comes out too high and cuts off the best plan.
+        let mut rng = XorShift64::new(0x1e12);
+        let limit = 3;
+        for _ in 0..10000 {
+            let near = |rng: &mut XorShift64| 1_000_000_000_000 + rng.range(-3..4);
+            let buttons: Vec<Button> = ['A', 'B', 'C', 'D']
+                .into_iter()
+                .map(|label| Button {
+                    label,
+                    step: (near(&mut rng), near(&mut rng)),
+                    cost: rng.range(1..6),
+                })
+                .collect();
+            // reachable, with some presses of each button
+            let prize = buttons.iter().fold((0, 0), |at, b| {
+                let n = rng.range(0..limit + 1);
+                (at.0 + n * b.step.0, at.1 + n * b.step.1)
+            });
+            let m = Machine { buttons, prize };
+            let want = brute_force(&m, limit);
+            assert_eq!(Ok(want), m.cheapest_path(Some(limit)), "{m:?}");
+        }
+    }
+
+    #[test]
+    fn three_buttons_part_two_sized() {
+        let mut m = parse_input(EXAMPLE.as_bytes()).swap_remove(0);
+        let c: Machine = "Button C: X+50, Y+50, Cost=2\nPrize: X=0, Y=0"
+            .parse()
+            .unwrap();
+        m.buttons.extend(c.buttons);
+        m.prize = (m.prize.0 + 10000000000000, m.prize.1 + 10000000000000);
+        let want = vec![81081081155, 108108108140, 15];
+        assert_eq!(Ok(Some((351351351635, want))), m.cheapest_presses(None));
+        // cheap enough to press as often as possible
+        m.buttons[2].cost = 1;
+        let want = vec![50, 0, 200000000074];
+        assert_eq!(Ok(Some((200000000224, want))), m.cheapest_presses(None));
+    }
+
+    #[test]
+    fn two_buttons_by_branching() {
+        // A free, motionless third button leaves A to the branch and bound
+        // and B to the closed form, which must agree with the closed form
+        // doing both.
+        let mut rng = XorShift64::new(0x2);
+        for _ in 0..2000 {
+            let mut c = claw(
+                (rng.range(-2..7), rng.range(-2..7)),
+                (rng.range(-2..7), rng.range(-2..7)),
+                (rng.range(-5..55), rng.range(-5..55)),
+            );
+            (c.cost_a, c.cost_b) = (rng.range(0..5), rng.range(0..5));
+            let button = |label, step, cost| Button { label, step, cost };
+            let m = Machine {
+                buttons: vec![
+                    button('A', c.button_a, c.cost_a),
+                    button('B', c.button_b, c.cost_b),
+                    button('C', (0, 0), 0),
+                ],
+                prize: c.prize,
+            };
+            let got = m.cheapest_path(Some(20)).unwrap();
+            assert_eq!(cheapest(&c, Some(20)), got, "{c:?}");
         }
     }
 }
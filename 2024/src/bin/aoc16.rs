@@ -1,6 +1,6 @@
+use aoc2024::search;
 use std::{
-    cmp::Reverse,
-    collections::{BinaryHeap, HashMap, HashSet},
+    collections::{HashMap, HashSet},
     fmt::{Display, Formatter, Write},
     fs::File,
     io::{BufRead, BufReader},
@@ -10,50 +10,122 @@ fn main() -> std::io::Result<()> {
     let path_input = args.get(1).expect("Should have an input file");
     // Part one
     let f = File::open(path_input)?;
-    let map = parse_input(BufReader::new(f));
+    let mut map = parse_input(BufReader::new(f));
+    map.costs = Costs::from_args(&args);
     let best_path_cost = find_cheapest_path(&map).expect("path should exist");
     dbg!(best_path_cost);
     // Part two
-    let p2 = do_part_two(&map);
-    dbg!(p2);
+    let (p2, best_path_count) = do_part_two(&map);
+    dbg!(p2, best_path_count);
+    if let Some(i) = args.iter().position(|a| a == "--within") {
+        let slack: u32 = args[i + 1].parse().expect("points over the best");
+        let seats = seats_within(&map, slack);
+        print!("{}", render(&map, |xy| seats.get(&xy).copied()));
+        let near_best_seats = seats.len();
+        dbg!(near_best_seats);
+    }
+    if let Some(i) = args.iter().position(|a| a == "--k-paths") {
+        let k: usize = args[i + 1].parse().expect("number of paths");
+        let paths = k_cheapest_routes(&map, k);
+        let mut ranks = HashMap::new();
+        for (rank, path) in paths.iter().enumerate().rev() {
+            println!("#{}: {}", rank + 1, path.cost);
+            let mark = char::from_digit(rank as u32 + 1, 10).unwrap_or('+');
+            for w in path.states.iter() {
+                ranks.insert((w.x, w.y), mark);
+            }
+        }
+        print!("{}", render(&map, |xy| ranks.get(&xy).copied()));
+    }
     Ok(())
 }
 
-fn parse_input(r: BufReader<File>) -> Map {
+fn parse_input(r: impl BufRead) -> Map {
     let mut tiles = vec![];
-    for line in r.lines() {
+    let (mut start_xy, mut end_xy) = (None, None);
+    for (y, line) in r.lines().enumerate() {
         let line = line.expect("sane input");
         let mut map_row = vec![];
-        for b in line.bytes() {
+        for (x, b) in line.bytes().enumerate() {
+            match b {
+                b'S' => start_xy = Some((x, y)),
+                b'E' => end_xy = Some((x, y)),
+                _ => {}
+            }
             let s = b.try_into().unwrap();
             map_row.push(s);
         }
         tiles.push(map_row);
     }
-    let width = tiles[0].len();
-    let height = tiles.len();
     Map {
         tiles,
-        width,
-        height,
-        start_xy: (1, height - 2),
+        start_xy: start_xy.expect("map should have an S"),
         start_direction: Direction::East,
-        end_xy: (width - 2, 1),
-        cost_straight: 1,
-        cost_turn: 1000,
+        end_xy: end_xy.expect("map should have an E"),
+        costs: Costs::default(),
+    }
+}
+
+/// What each kind of move costs; `None` forbids it.
+#[derive(Clone, Debug)]
+struct Costs {
+    straight: u32,
+    /// A quarter turn on the spot.
+    turn: u32,
+    /// A half turn on the spot as a single move, instead of two quarter turns.
+    u_turn: Option<u32>,
+    /// One step backwards, still facing the same way.
+    reverse: Option<u32>,
+    /// One step forward and one to either side at once, still facing the same
+    /// way.  Cannot squeeze between two walls touching at their corners.
+    diagonal: Option<u32>,
+}
+
+impl Default for Costs {
+    /// As in the puzzle.
+    fn default() -> Self {
+        Self {
+            straight: 1,
+            turn: 1000,
+            u_turn: None,
+            reverse: None,
+            diagonal: None,
+        }
+    }
+}
+
+impl Costs {
+    /// `--cost-straight N`, `--cost-turn N`, `--cost-u-turn N`,
+    /// `--cost-reverse N` and `--cost-diagonal N`.  Every cost must be
+    /// positive, or part two misses best paths.
+    fn from_args(args: &[String]) -> Self {
+        let flag = |name: &str| {
+            let i = args.iter().position(|a| a == name)?;
+            let cost: u32 = args
+                .get(i + 1)
+                .and_then(|v| v.parse().ok())
+                .expect("cost should be a number");
+            assert!(cost > 0, "{name} should be positive");
+            Some(cost)
+        };
+        let puzzle = Self::default();
+        Self {
+            straight: flag("--cost-straight").unwrap_or(puzzle.straight),
+            turn: flag("--cost-turn").unwrap_or(puzzle.turn),
+            u_turn: flag("--cost-u-turn"),
+            reverse: flag("--cost-reverse"),
+            diagonal: flag("--cost-diagonal"),
+        }
     }
 }
 
 #[derive(Clone, Debug)]
 struct Map {
     tiles: Vec<Vec<MapTile>>,
-    width: usize,
-    height: usize,
     start_xy: (usize, usize),
     start_direction: Direction,
     end_xy: (usize, usize),
-    cost_straight: u32,
-    cost_turn: u32,
+    costs: Costs,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -103,16 +175,21 @@ impl Direction {
         }
     }
 
-    fn as_bitmask(&self) -> u8 {
-        1 << (*self as u32)
-    }
-
     const ENUM_VARIANTS: u8 = 4;
     fn iter() -> impl Iterator<Item = Direction> {
         // Safety: as long as `ENUM_VARIANTS` is accurate; relies on `repr(u8)`
         (0..Self::ENUM_VARIANTS).map(|e| unsafe { std::mem::transmute(e) })
     }
 
+    fn reversed(&self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+            Direction::East => Direction::West,
+        }
+    }
+
     fn both_turns(&self) -> [Direction; 2] {
         match self {
             Direction::North | Direction::South => [Direction::East, Direction::West],
@@ -124,142 +201,417 @@ impl Direction {
 #[derive(Debug, PartialEq, Eq)]
 struct AOCParseError;
 
-#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
-struct StateAStar {
-    cost: u32,
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct Where {
     x: usize,
     y: usize,
     d: Direction,
 }
 
-fn find_cheapest_path(map: &Map) -> Option<u32> {
-    let mut seen = vec![vec![0_u8; map.width]; map.height];
-    let mut min_heap = BinaryHeap::new();
-    min_heap.push(Reverse(StateAStar {
-        cost: 0,
-        x: map.start_xy.0,
-        y: map.start_xy.1,
-        d: map.start_direction,
-    }));
-    while let Some(Reverse(StateAStar { cost, x, y, d })) = min_heap.pop() {
-        if map.end_xy == (x, y) {
-            return Some(cost);
-        }
-        let mask = d.as_bitmask();
-        if seen[y][x] & mask != 0 {
-            continue;
+impl Map {
+    fn start(&self) -> Where {
+        Where {
+            x: self.start_xy.0,
+            y: self.start_xy.1,
+            d: self.start_direction,
         }
-        seen[y][x] |= mask;
-        // Safety: entire map is surrounded with Walls.
-        let (dx, dy) = d.as_wrapping_dxdy();
-        // Straight
-        let (xx, yy) = (x.wrapping_add(dx), y.wrapping_add(dy));
-        if map.tiles[yy][xx] == MapTile::Open {
-            // Not needed, but reduces churn.
-            if seen[yy][xx] & mask == 0 {
-                min_heap.push(Reverse(StateAStar {
-                    cost: cost + map.cost_straight,
-                    x: xx,
-                    y: yy,
-                    d,
-                }));
+    }
+
+    fn ends(&self) -> impl Iterator<Item = Where> + '_ {
+        Direction::iter().map(|d| Where {
+            x: self.end_xy.0,
+            y: self.end_xy.1,
+            d,
+        })
+    }
+
+    fn is_open(&self, (x, y): (usize, usize)) -> bool {
+        self.tiles.get(y).and_then(|row| row.get(x)) == Some(&MapTile::Open)
+    }
+
+    /// Every move allowed by `costs`, whether or not it is useful.
+    fn moves(&self, w: &Where) -> Vec<(Where, u32)> {
+        let step = |(x, y): (usize, usize), d: Direction| {
+            let (dx, dy) = d.as_wrapping_dxdy();
+            (x.wrapping_add(dx), y.wrapping_add(dy))
+        };
+        let costs = &self.costs;
+        let mut moves = vec![];
+        let mut go = |(x, y), cost| {
+            if self.is_open((x, y)) {
+                moves.push((Where { x, y, d: w.d }, cost));
             }
+        };
+        let forward = step((w.x, w.y), w.d);
+        go(forward, costs.straight);
+        if let Some(cost) = costs.reverse {
+            go(step((w.x, w.y), w.d.reversed()), cost);
         }
-        // Rotations
-        for dd in Direction::iter() {
-            if d == dd {
-                continue;
-            }
-            let maskmask = dd.as_bitmask();
-            // also reduces churn
-            if seen[y][x] & maskmask != 0 {
-                continue;
+        if let Some(cost) = costs.diagonal {
+            for side in w.d.both_turns() {
+                let beside = step((w.x, w.y), side);
+                if self.is_open(forward) || self.is_open(beside) {
+                    go(step(forward, side), cost);
+                }
             }
-            min_heap.push(Reverse(StateAStar {
-                cost: cost + map.cost_turn,
-                x,
-                y,
-                d: dd,
-            }));
         }
+        for d in w.d.both_turns() {
+            moves.push((Where { d, ..*w }, costs.turn));
+        }
+        if let Some(cost) = costs.u_turn {
+            moves.push((
+                Where {
+                    d: w.d.reversed(),
+                    ..*w
+                },
+                cost,
+            ));
+        }
+        moves
+    }
+
+    /// Consistent, as [`search::astar`] needs: no move covers ground more
+    /// cheaply than the cheapest one, and when the only way to move is
+    /// forwards, the turns needed to face every way still to go drop by at
+    /// most one per turn.  Reverse and diagonal moves get around turning, so
+    /// with those only the ground counts.
+    fn heuristic(&self, w: &Where) -> u32 {
+        let (dx, dy) = (w.x.abs_diff(self.end_xy.0), w.y.abs_diff(self.end_xy.1));
+        let distance = (dx + dy) as u32;
+        let costs = &self.costs;
+        let ground = [
+            Some(distance * costs.straight),
+            costs.reverse.map(|c| distance * c),
+            costs.diagonal.map(|c| distance * c / 2),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+        .expect("straight is always allowed");
+        if costs.reverse.is_some() || costs.diagonal.is_some() {
+            return ground;
+        }
+        let towards = |from: usize, to: usize, less, more| {
+            (from != to).then_some(if from < to { more } else { less })
+        };
+        let needed = [
+            towards(w.x, self.end_xy.0, Direction::West, Direction::East),
+            towards(w.y, self.end_xy.1, Direction::North, Direction::South),
+        ];
+        let needed: Vec<Direction> = needed.into_iter().flatten().collect();
+        let turns: u32 = match needed[..] {
+            [] => 0,
+            [only] if only == w.d => 0,
+            [only] if only == w.d.reversed() => 2,
+            [_] => 1,
+            _ if needed.contains(&w.d) => 1,
+            _ => 2,
+        };
+        // a cheap enough U-turn stands in for either
+        let turning = (turns * costs.turn).min(costs.u_turn.unwrap_or(u32::MAX));
+        ground + turning
     }
-    None
 }
 
-#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
-struct StateAStarWithHistory {
-    cost: u32,
-    d: Direction,
-    history: Vec<(usize, usize)>,
+fn find_cheapest_path(map: &Map) -> Option<u32> {
+    search::astar(
+        map.start(),
+        |w| map.moves(w),
+        |w| map.heuristic(w),
+        |w| (w.x, w.y) == map.end_xy,
+    )
+    .map(|path| path.cost)
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-struct Where {
-    x: usize,
-    y: usize,
-    d: Direction,
+/// Every tile on at least one best path, and how many best paths there are.
+fn do_part_two(map: &Map) -> (u64, u128) {
+    let mut search = search::Search::new([map.start()]);
+    let _never = search.run(|w| map.moves(w), |_| 0, |_| false);
+    let ends: Vec<Where> = map.ends().collect();
+    let best_paths = search.cheapest_paths(ends.iter());
+    let best_seats: HashSet<(usize, usize)> = best_paths.states().map(|w| (w.x, w.y)).collect();
+    (best_seats.len() as u64, best_paths.count_paths())
 }
 
-fn do_part_two(map: &Map) -> u64 {
-    let mut min_heap = BinaryHeap::new();
-    min_heap.push(Reverse(StateAStarWithHistory {
-        cost: 0,
-        d: map.start_direction,
-        history: vec![map.start_xy],
-    }));
-    let mut best_costs = HashMap::new();
-    best_costs.insert(
-        Where {
-            x: map.start_xy.0,
-            y: map.start_xy.1,
-            d: map.start_direction,
-        },
-        0,
-    );
-    let mut best_path_cost = u32::MAX;
-    let mut best_seats = HashSet::new();
-    while let Some(Reverse(StateAStarWithHistory { cost, d, history })) = min_heap.pop() {
-        let (x, y) = *history.last().expect("initialized");
-        if (x, y) == map.end_xy {
-            best_path_cost = cost;
-            for &(xx, yy) in history.iter() {
-                best_seats.insert((xx, yy));
-            }
-            continue;
+/// Tiles on some walk from start to end costing at most `slack` more than
+/// the best one: `O` if on a best path, `o` otherwise.
+fn seats_within(map: &Map, slack: u32) -> HashMap<(usize, usize), char> {
+    let from_start = search::distances([map.start()], |w| map.moves(w));
+    let mut reversed: HashMap<Where, Vec<(Where, u32)>> = HashMap::new();
+    for &w in from_start.keys() {
+        for (next, cost) in map.moves(&w) {
+            reversed.entry(next).or_default().push((w, cost));
         }
-        if cost >= best_path_cost {
-            // not `break` because of "if end" above
+    }
+    let to_end = search::distances(map.ends(), |w| {
+        reversed.get(w).into_iter().flatten().copied()
+    });
+    let Some(&best) = map.ends().filter_map(|w| from_start.get(&w)).min() else {
+        return HashMap::new();
+    };
+    let mut seats = HashMap::new();
+    for (w, cost) in from_start.iter() {
+        let Some(rest) = to_end.get(w) else {
             continue;
+        };
+        let over = cost + rest - best;
+        if over <= slack {
+            let mark = if over == 0 { 'O' } else { 'o' };
+            let seat = seats.entry((w.x, w.y)).or_insert(mark);
+            if mark == 'O' {
+                *seat = mark;
+            }
+        }
+    }
+    seats
+}
+
+/// The `k` cheapest routes that never repeat a tile and heading, cheapest
+/// first.  Routes differing only in which way round a U-turn goes count as
+/// different.
+fn k_cheapest_routes(map: &Map, k: usize) -> Vec<search::Path<Where, u32>> {
+    search::k_cheapest_paths(
+        map.start(),
+        k,
+        |w| map.moves(w),
+        |w| map.heuristic(w),
+        |w| (w.x, w.y) == map.end_xy,
+    )
+}
+
+/// The maze with `mark` drawn over any tile it has an opinion on.
+fn render(map: &Map, mark: impl Fn((usize, usize)) -> Option<char>) -> String {
+    let mut s = String::new();
+    for (y, row) in map.tiles.iter().enumerate() {
+        for (x, tile) in row.iter().enumerate() {
+            match mark((x, y)) {
+                Some(c) => s.push(c),
+                None => write!(s, "{tile}").expect("infallible"),
+            }
+        }
+        s.push('\n');
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc2024::rng::XorShift64;
+
+    const EXAMPLE: &str = "\
+###############
+#.......#....E#
+#.#.###.#.###.#
+#.....#.#...#.#
+#.###.#####.#.#
+#.#.#.......#.#
+#.#.#####.###.#
+#...........#.#
+###.#.#####.#.#
+#...#.....#.#.#
+#.#.#.###.#.#.#
+#.....#...#.#.#
+#.###.#.#.#.#.#
+#S..#.....#...#
+###############
+";
+
+    #[test]
+    fn best_seats() {
+        let map = parse_input(EXAMPLE.as_bytes());
+        assert_eq!(Some(7036), find_cheapest_path(&map));
+        assert_eq!((45, 3), do_part_two(&map));
+        let mut search = search::Search::new([map.start()]);
+        let _never = search.run(|w| map.moves(w), |_| 0, |_| false);
+        let ends: Vec<Where> = map.ends().collect();
+        let paths: Vec<Vec<Where>> = search.cheapest_paths(ends.iter()).paths().collect();
+        assert_eq!(3, paths.len());
+        let seats: HashSet<(usize, usize)> = paths.iter().flatten().map(|w| (w.x, w.y)).collect();
+        assert_eq!(45, seats.len());
+    }
+
+    #[test]
+    fn cost_models() {
+        let mut map = parse_input(EXAMPLE.as_bytes());
+        map.costs.u_turn = Some(2000);
+        map.costs.reverse = Some(5000);
+        assert_eq!(Some(7036), find_cheapest_path(&map));
+        for costs in [
+            Costs {
+                diagonal: Some(2),
+                ..Costs::default()
+            },
+            Costs {
+                straight: 2,
+                turn: 7,
+                u_turn: Some(5),
+                reverse: Some(1),
+                diagonal: Some(3),
+            },
+        ] {
+            map.costs = costs;
+            let mut search = search::Search::new([map.start()]);
+            let _never = search.run(|w| map.moves(w), |_| 0, |_| false);
+            let ends: Vec<Where> = map.ends().collect();
+            let best_paths = search.cheapest_paths(ends.iter());
+            assert_eq!(best_paths.cost(), find_cheapest_path(&map));
+            assert!(best_paths.cost() < Some(7036));
+            assert_eq!(best_paths.count_paths(), best_paths.paths().count() as u128);
+        }
+    }
+
+    #[test]
+    fn near_best() {
+        let map = parse_input(EXAMPLE.as_bytes());
+        let seats = seats_within(&map, 0);
+        assert_eq!(45, seats.len());
+        assert!(seats.values().all(|&c| c == 'O'));
+        let seats = seats_within(&map, 4000);
+        assert_eq!(66, seats.len());
+        assert_eq!(45, seats.values().filter(|&&c| c == 'O').count());
+        let routes = k_cheapest_routes(&map, 5);
+        let costs: Vec<u32> = routes.iter().map(|p| p.cost).collect();
+        assert_eq!(vec![7036; 3], costs[..3]);
+        assert!(costs.is_sorted() && costs[3] > 7036);
+        let drawn = render(&map, |xy| seats.get(&xy).copied());
+        assert_eq!(EXAMPLE.len(), drawn.len());
+    }
+
+    /// A walled `size` by `size` maze, about a third of it wall, with S and
+    /// E in random open spots.
+    fn random_maze(rng: &mut XorShift64, size: usize) -> Map {
+        let mut rows: Vec<Vec<u8>> = (0..size)
+            .map(|y| {
+                (0..size)
+                    .map(|x| {
+                        let edge = x == 0 || y == 0 || x == size - 1 || y == size - 1;
+                        if edge || rng.below(3) == 0 {
+                            b'#'
+                        } else {
+                            b'.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        for c in [b'S', b'E'] {
+            let (x, y) = (1 + rng.below(size - 2), 1 + rng.below(size - 2));
+            rows[y][x] = c;
+        }
+        if !rows.iter().flatten().any(|&c| c == b'S') {
+            // E landed on top of S
+            rows[1][1] = b'S';
+            rows[size - 2][size - 2] = b'E';
+        }
+        let text: Vec<u8> = rows.join(&b'\n').into_iter().chain([b'\n']).collect();
+        parse_input(&text[..])
+    }
+
+    fn cost_model(rng: &mut XorShift64) -> Costs {
+        let mut maybe = |most: usize| {
+            let cost = 1 + rng.below(most) as u32;
+            (rng.below(2) == 0).then_some(cost)
+        };
+        let (u_turn, reverse, diagonal) = (maybe(3000), maybe(20), maybe(6));
+        Costs {
+            straight: 1 + rng.below(3) as u32,
+            turn: [1, 7, 1000][rng.below(3)],
+            u_turn,
+            reverse,
+            diagonal,
         }
-        // Now, we either have not found the end; or have, but `cost` is too low.
-        let mut do_next = |new_cost, xx, yy, dd| {
-            let row: &Vec<MapTile> = &map.tiles[yy];
-            if row[xx] != MapTile::Open {
-                return;
+    }
+
+    #[test]
+    fn astar_agrees_with_dijkstra() {
+        let mut rng = XorShift64::new(0x16);
+        for round in 0..300 {
+            let size = 4 + rng.below(10);
+            let mut map = random_maze(&mut rng, size);
+            if round % 2 == 1 {
+                map.costs = cost_model(&mut rng);
+            }
+            let plain =
+                search::dijkstra(map.start(), |w| map.moves(w), |w| (w.x, w.y) == map.end_xy);
+            assert_eq!(plain.map(|p| p.cost), find_cheapest_path(&map), "{map:?}");
+            // consistency, which is what A* with a closed set needs
+            for w in search::distances([map.start()], |w| map.moves(w)).into_keys() {
+                for (next, cost) in map.moves(&w) {
+                    assert!(
+                        map.heuristic(&w) <= cost + map.heuristic(&next),
+                        "{w:?} {next:?}"
+                    );
+                }
             }
-            let p = Where {
-                x: xx,
-                y: yy,
-                d: dd,
-            };
-            let costcost = best_costs.entry(p).or_insert(u32::MAX);
-            if new_cost <= *costcost {
-                *costcost = new_cost;
-                let mut history = history.clone();
-                history.push((xx, yy));
-                min_heap.push(Reverse(StateAStarWithHistory {
-                    cost: new_cost,
-                    d: dd,
-                    history,
-                }));
+        }
+    }
+
+    /// Costs of the `k` cheapest walks to the end that never repeat a state
+    /// and stop on first reaching the end: every such walk, but cut short
+    /// once even the cheapest way on cannot beat the `k`th found so far.
+    fn cheapest_route_costs(map: &Map, k: usize) -> Vec<u32> {
+        struct Walk<'a> {
+            map: &'a Map,
+            k: usize,
+            to_end: HashMap<Where, u32>,
+            seen: HashSet<Where>,
+            /// sorted, at most `k`
+            found: Vec<u32>,
+        }
+        impl Walk<'_> {
+            fn go(&mut self, at: Where, cost: u32) {
+                let Some(&rest) = self.to_end.get(&at) else {
+                    return;
+                };
+                if self.found.len() == self.k && cost + rest >= self.found[self.k - 1] {
+                    return;
+                }
+                if (at.x, at.y) == self.map.end_xy {
+                    let i = self.found.partition_point(|&c| c <= cost);
+                    self.found.insert(i, cost);
+                    self.found.truncate(self.k);
+                    return;
+                }
+                for (next, step) in self.map.moves(&at) {
+                    if self.seen.insert(next) {
+                        self.go(next, cost + step);
+                        self.seen.remove(&next);
+                    }
+                }
             }
+        }
+        let states = search::distances([map.start()], |w| map.moves(w));
+        let to_end = states
+            .into_keys()
+            .filter_map(|w| {
+                let path = search::dijkstra(w, |w| map.moves(w), |w| (w.x, w.y) == map.end_xy);
+                Some((w, path?.cost))
+            })
+            .collect();
+        let mut walk = Walk {
+            map,
+            k,
+            to_end,
+            seen: HashSet::from([map.start()]),
+            found: vec![],
         };
-        let (dx, dy) = d.as_wrapping_dxdy();
-        let (x_s, y_s) = (x.wrapping_add(dx), y.wrapping_add(dy));
-        do_next(cost + map.cost_straight, x_s, y_s, d);
-        let d_turn = d.both_turns();
-        do_next(cost + map.cost_turn, x, y, d_turn[0]);
-        do_next(cost + map.cost_turn, x, y, d_turn[1]);
+        walk.go(map.start(), 0);
+        walk.found
+    }
+
+    #[test]
+    fn k_cheapest_matches_brute_force() {
+        let mut rng = XorShift64::new(0x36);
+        for round in 0..100 {
+            let size = 5 + rng.below(3);
+            let mut map = random_maze(&mut rng, size);
+            if round % 2 == 1 {
+                map.costs = cost_model(&mut rng);
+            }
+            let want = cheapest_route_costs(&map, 8);
+            let got: Vec<u32> = k_cheapest_routes(&map, 8).iter().map(|p| p.cost).collect();
+            assert_eq!(want, got, "{map:?}");
+        }
     }
-    best_seats.len() as u64
 }
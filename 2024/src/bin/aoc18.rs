@@ -1,3 +1,4 @@
+use aoc2024::search;
 use std::{
     fs::File,
     io::{BufRead, BufReader},
@@ -18,15 +19,33 @@ fn main() -> std::io::Result<()> {
     let mut world = vec![vec![false; width]; height];
     simulate(&mut world, &coords[0..steps]);
     // println!("{world:?}");
-    let p1 = find_length_shortest_path(&world);
+    let path = find_shortest_path(&world);
+    let p1 = path.as_ref().map(|p| p.len() - 1);
     dbg!(p1);
+    let is_render = args.iter().any(|a| a == "--render");
+    if is_render {
+        print!(
+            "{}",
+            render(&world, path.as_deref().unwrap_or_default(), &[])
+        );
+    }
     // Part two
-    for another_step in steps..coords.len() {
-        simulate(&mut world, &coords[another_step..another_step + 1]);
-        let has_path = find_length_shortest_path(&world);
-        if has_path.is_none() {
-            println!("{},{}", coords[another_step].0, coords[another_step].1);
-            break;
+    let first_blocking = if args.iter().any(|a| a == "--bisect") {
+        first_blocking_byte_bisect(&coords, height, width)
+    } else if args.iter().any(|a| a == "--brute-force") {
+        first_blocking_byte_brute_force(&coords, height, width)
+    } else {
+        first_blocking_byte(&coords, height, width)
+    };
+    if let Some(i) = first_blocking {
+        println!("{},{}", coords[i].0, coords[i].1);
+        if is_render {
+            let mut world = vec![vec![false; width]; height];
+            simulate(&mut world, &coords[..i]);
+            let last_path = find_shortest_path(&world).expect("not blocked yet");
+            simulate(&mut world, &coords[i..=i]);
+            let cut = find_cut(&world).expect("blocked now");
+            print!("{}", render(&world, &last_path, &cut));
         }
     }
     Ok(())
@@ -50,37 +69,189 @@ fn simulate(world: &mut Vec<Vec<bool>>, coords: &[(usize, usize)]) {
 }
 
 fn find_length_shortest_path(world: &Vec<Vec<bool>>) -> Option<u32> {
+    find_shortest_path(world).map(|path| (path.len() - 1) as u32)
+}
+
+/// Includes both corners.
+fn find_shortest_path(world: &[Vec<bool>]) -> Option<Vec<(usize, usize)>> {
     let height = world.len();
     let width = world[0].len();
     let goal = (width - 1, height - 1);
-    let mut seen = world.clone(); // have already seen the obstacles
-    let mut active = vec![];
-    active.push((0, 0));
-    let mut future = vec![];
-    let mut steps = 0;
-    while !active.is_empty() {
-        for (x, y) in active.drain(..) {
-            if (x, y) == goal {
-                return Some(steps);
-            }
-            if seen[y][x] {
-                continue;
+    if world[0][0] {
+        return None;
+    }
+    search::astar(
+        (0, 0),
+        |&(x, y)| {
+            NeighborIterator::new(height, width, x, y, false)
+                .filter(|&(xx, yy)| !world[yy][xx])
+                .map(|xy| (xy, 1))
+        },
+        |&(x, y)| (goal.0 - x + goal.1 - y) as u32,
+        |&xy| xy == goal,
+    )
+    .map(|path| path.states)
+}
+
+/// Shortest chain of bytes, touching at edges or corners, from the top or
+/// right edge to the left or bottom edge.  There is one exactly when no path
+/// gets through, and it must include the byte that blocked the last path.
+fn find_cut(world: &[Vec<bool>]) -> Option<Vec<(usize, usize)>> {
+    let height = world.len();
+    let width = world[0].len();
+    let top_right = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter(|&(x, y)| world[y][x] && (y == 0 || x == width - 1));
+    let mut search = search::Search::new(top_right);
+    search
+        .run(
+            |&(x, y)| {
+                NeighborIterator::new(height, width, x, y, true)
+                    .filter(|&(xx, yy)| world[yy][xx])
+                    .map(|xy| (xy, 1_u32))
+            },
+            |_| 0,
+            |&(x, y)| x == 0 || y == height - 1,
+        )
+        .map(|path| path.states)
+}
+
+/// `O` for the path, `X` for the cut, `#` for any other byte.
+fn render(world: &[Vec<bool>], path: &[(usize, usize)], cut: &[(usize, usize)]) -> String {
+    let mut s = String::new();
+    for (y, row) in world.iter().enumerate() {
+        for (x, &is_byte) in row.iter().enumerate() {
+            s.push(if cut.contains(&(x, y)) {
+                'X'
+            } else if is_byte {
+                '#'
+            } else if path.contains(&(x, y)) {
+                'O'
+            } else {
+                '.'
+            });
+        }
+        s.push('\n');
+    }
+    s
+}
+
+/// Index of the first byte after which there is no way through, by adding
+/// bytes one at a time and searching again after each.
+fn first_blocking_byte_brute_force(
+    coords: &[(usize, usize)],
+    height: usize,
+    width: usize,
+) -> Option<usize> {
+    let mut world = vec![vec![false; width]; height];
+    for (i, c) in coords.iter().enumerate() {
+        simulate(&mut world, std::slice::from_ref(c));
+        if find_length_shortest_path(&world).is_none() {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Same answer, by binary search over how many bytes have fallen: once
+/// blocked, always blocked.
+fn first_blocking_byte_bisect(
+    coords: &[(usize, usize)],
+    height: usize,
+    width: usize,
+) -> Option<usize> {
+    let fallen = (1..=coords.len()).collect::<Vec<_>>();
+    let n = fallen.partition_point(|&n| {
+        let mut world = vec![vec![false; width]; height];
+        simulate(&mut world, &coords[..n]);
+        find_length_shortest_path(&world).is_some()
+    });
+    fallen.get(n).map(|n| n - 1)
+}
+
+/// Same answer again, offline: let every byte fall, then take them back out
+/// last to first, joining each freed tile to its open neighbors, until the
+/// corners end up in the same set.
+fn first_blocking_byte(coords: &[(usize, usize)], height: usize, width: usize) -> Option<usize> {
+    let index = |(x, y): (usize, usize)| y * width + x;
+    let mut fallen_at = vec![None; height * width];
+    for (i, &c) in coords.iter().enumerate().rev() {
+        // only the first byte onto each tile matters
+        fallen_at[index(c)] = Some(i);
+    }
+    let mut sets = DisjointSets::new(height * width);
+    let free = |sets: &mut DisjointSets, fallen_at: &[Option<usize>], (x, y)| {
+        for (xx, yy) in NeighborIterator::new(height, width, x, y, false) {
+            if fallen_at[index((xx, yy))].is_none() {
+                sets.union(index((x, y)), index((xx, yy)));
             }
-            seen[y][x] = true;
-            for (xx, yy) in NeighborIterator::new(height, width, x, y, false) {
-                // reduces churn
-                if seen[yy][xx] {
-                    continue;
-                }
-                future.push((xx, yy));
+        }
+    };
+    for y in 0..height {
+        for x in 0..width {
+            if fallen_at[index((x, y))].is_none() {
+                free(&mut sets, &fallen_at, (x, y));
             }
         }
-        std::mem::swap(&mut active, &mut future);
-        steps += 1;
     }
-    None
+    let (start, goal) = (index((0, 0)), index((width - 1, height - 1)));
+    let is_open = |sets: &mut DisjointSets, fallen_at: &[Option<usize>]| {
+        fallen_at[start].is_none()
+            && fallen_at[goal].is_none()
+            && sets.find(start) == sets.find(goal)
+    };
+    if is_open(&mut sets, &fallen_at) {
+        return None;
+    }
+    for (i, &c) in coords.iter().enumerate().rev() {
+        if fallen_at[index(c)] != Some(i) {
+            continue;
+        }
+        fallen_at[index(c)] = None;
+        free(&mut sets, &fallen_at, c);
+        if is_open(&mut sets, &fallen_at) {
+            return Some(i);
+        }
+    }
+    unreachable!("an empty grid has a path")
+}
+
+//region Disjoint-set forest with union by size and path halving.
+struct DisjointSets {
+    parent: Vec<usize>,
+    size: Vec<usize>,
 }
 
+impl DisjointSets {
+    fn new(n: usize) -> Self {
+        Self {
+            parent: (0..n).collect(),
+            size: vec![1; n],
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+    }
+}
+//endregion
+
 //region Iterator over neighboring tiles in a grid; four- or eight-connected.
 struct NeighborIterator {
     height: isize,
@@ -142,3 +313,44 @@ impl Iterator for NeighborIterator {
     }
 }
 //endregion
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc2024::rng::XorShift64;
+
+    #[test]
+    fn first_blocking_byte_agrees() {
+        let mut rng = XorShift64::new(0x18);
+        for trial in 0..200 {
+            let (height, width) = (1 + rng.below(9), 1 + rng.below(9));
+            // some repeats, sometimes too few to ever block
+            let coords: Vec<(usize, usize)> = (0..rng.below(height * width + 5))
+                .map(|_| (rng.below(width), rng.below(height)))
+                .collect();
+            let want = first_blocking_byte_brute_force(&coords, height, width);
+            assert_eq!(
+                want,
+                first_blocking_byte_bisect(&coords, height, width),
+                "{trial}"
+            );
+            assert_eq!(want, first_blocking_byte(&coords, height, width), "{trial}");
+            let mut world = vec![vec![false; width]; height];
+            for (i, c) in coords.iter().enumerate() {
+                simulate(&mut world, std::slice::from_ref(c));
+                let path = find_shortest_path(&world);
+                let cut = find_cut(&world);
+                assert_eq!(path.is_some(), cut.is_none(), "{trial}");
+                if Some(i) == want {
+                    assert!(cut.is_some_and(|cut| cut.contains(c)), "{trial}");
+                }
+                if let Some(path) = path {
+                    assert!(path.iter().all(|&(x, y)| !world[y][x]));
+                    assert!(path
+                        .windows(2)
+                        .all(|w| w[0].0.abs_diff(w[1].0) + w[0].1.abs_diff(w[1].1) == 1));
+                }
+            }
+        }
+    }
+}
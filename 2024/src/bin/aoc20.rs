@@ -1,8 +1,9 @@
+use aoc2024::search;
 use std::{
+    collections::BTreeMap,
     fmt::{Display, Formatter, Write},
     fs::File,
     io::{BufRead, BufReader},
-    sync::LazyLock,
 };
 fn main() -> std::io::Result<()> {
     let args: Vec<String> = std::env::args().collect();
@@ -20,10 +21,29 @@ fn main() -> std::io::Result<()> {
     // Part two
     let p2 = map.good_cheats(&distances_start, &distances_end, 20, 100);
     dbg!(p2);
+    // Anything else
+    let radius_and_min = |flag: &str| {
+        let i = args.iter().position(|a| a == flag)?;
+        let radius: usize = args[i + 1].parse().expect("cheat radius");
+        let min_savings: u32 = args[i + 2].parse().expect("minimum savings");
+        Some((radius, min_savings))
+    };
+    if let Some((radius, min_savings)) = radius_and_min("--histogram") {
+        let histogram =
+            map.savings_histogram(&distances_start, &distances_end, radius, min_savings);
+        for (savings, count) in histogram {
+            println!("{count} cheats save {savings} picoseconds");
+        }
+    }
+    if let Some((radius, min_savings)) = radius_and_min("--list") {
+        map.for_each_cheat(&distances_start, &distances_end, radius, min_savings, |c| {
+            println!("{:?} -> {:?} saves {}", c.start, c.end, c.savings);
+        });
+    }
     Ok(())
 }
 
-fn parse_input(r: BufReader<File>) -> Map {
+fn parse_input(r: impl BufRead) -> Map {
     let mut tiles = vec![];
     let mut start_xy = (0, 0);
     let mut end_xy = (0, 0);
@@ -105,37 +125,17 @@ impl Map {
         self.distances_from(self.end_xy)
     }
 
+    /// `u32::MAX` for walls and anything else out of reach.
     fn distances_from(&self, xy: (usize, usize)) -> Vec<Vec<u32>> {
-        let mut distances = vec![vec![0; self.width]; self.height];
-        for (t_row, d_row) in self.tiles.iter().zip(distances.iter_mut()) {
-            for (t, d) in t_row.iter().zip(d_row.iter_mut()) {
-                if *t == MapTile::Wall {
-                    *d = u32::MAX;
-                }
-            }
-        }
-        let mut active = vec![];
-        active.push(xy);
-        let mut future = vec![];
-        let mut steps = 0;
-        while !active.is_empty() {
-            for (x, y) in active.drain(..) {
-                if distances[y][x] > 0 {
-                    continue;
-                }
-                distances[y][x] = steps;
-                for (xx, yy) in NeighborIterator::new(self.height, self.width, x, y, false) {
-                    // reduces churn
-                    if distances[yy][xx] > 0 {
-                        continue;
-                    }
-                    future.push((xx, yy));
-                }
-            }
-            std::mem::swap(&mut active, &mut future);
-            steps += 1;
+        let reachable = search::distances([xy], |&(x, y)| {
+            NeighborIterator::new(self.height, self.width, x, y, false)
+                .filter(|&(xx, yy)| self.tiles[yy][xx] != MapTile::Wall)
+                .map(|xxyy| (xxyy, 1))
+        });
+        let mut distances = vec![vec![u32::MAX; self.width]; self.height];
+        for ((x, y), d) in reachable {
+            distances[y][x] = d;
         }
-        distances[xy.1][xy.0] = 0; // avoids condition in loop
         distances
     }
 
@@ -163,76 +163,106 @@ impl Map {
 
     fn good_cheats(
         &self,
-        distances_start: &Vec<Vec<u32>>,
-        distances_end: &Vec<Vec<u32>>,
+        distances_start: &[Vec<u32>],
+        distances_end: &[Vec<u32>],
         cheat_distance: usize,
         min_savings: u32,
     ) -> u64 {
         let mut good_cheats = 0;
+        self.for_each_cheat(
+            distances_start,
+            distances_end,
+            cheat_distance,
+            min_savings,
+            |_| good_cheats += 1,
+        );
+        good_cheats
+    }
+
+    /// How many cheats save each amount of time.
+    fn savings_histogram(
+        &self,
+        distances_start: &[Vec<u32>],
+        distances_end: &[Vec<u32>],
+        cheat_distance: usize,
+        min_savings: u32,
+    ) -> BTreeMap<u32, u64> {
+        let mut histogram = BTreeMap::new();
+        self.for_each_cheat(
+            distances_start,
+            distances_end,
+            cheat_distance,
+            min_savings,
+            |cheat| *histogram.entry(cheat.savings).or_default() += 1,
+        );
+        histogram
+    }
+
+    /// Every cheat of at most `cheat_distance` picoseconds that gets to the
+    /// end at least `min_savings` sooner than the best honest route.  Both ends
+    /// of a cheat are on the track; which way the track goes in between does
+    /// not matter, so it may branch.
+    fn for_each_cheat<F>(
+        &self,
+        distances_start: &[Vec<u32>],
+        distances_end: &[Vec<u32>],
+        cheat_distance: usize,
+        min_savings: u32,
+        mut f: F,
+    ) where
+        F: FnMut(Cheat),
+    {
         let distance_no_cheat = distances_start[self.end_xy.1][self.end_xy.0];
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let (t, ds) = (self.tiles[y][x], distances_start[y][x]);
-                if t == MapTile::Wall {
+        if distance_no_cheat == u32::MAX {
+            return;
+        }
+        for (y, (t_row, ds_row)) in self.tiles.iter().zip(distances_start).enumerate() {
+            for (x, (&t, &ds)) in t_row.iter().zip(ds_row).enumerate() {
+                if t == MapTile::Wall || ds == u32::MAX {
                     continue;
                 }
                 self.for_each_nonwall_within((x, y), cheat_distance, |xx, yy| {
                     let de = distances_end[yy][xx];
-                    let dx = x.max(xx) - x.min(xx);
-                    let dy = y.max(yy) - y.min(yy);
-                    let d = (dx + dy) as u32;
+                    if de == u32::MAX {
+                        return;
+                    }
+                    let d = (x.abs_diff(xx) + y.abs_diff(yy)) as u32;
                     let distance_with_cheat = ds + d + de;
                     if distance_with_cheat + min_savings <= distance_no_cheat {
-                        good_cheats += 1;
+                        f(Cheat {
+                            start: (x, y),
+                            end: (xx, yy),
+                            savings: distance_no_cheat - distance_with_cheat,
+                        });
                     }
                 });
             }
         }
-        good_cheats
     }
 
     fn for_each_nonwall_within<F>(&self, xy: (usize, usize), distance: usize, mut f: F)
     where
         F: FnMut(usize, usize),
     {
-        let dxdy = match distance {
-            2 => &DISTANCE_L1_2,
-            20 => &DISTANCE_L1_20,
-            _ => panic!("not cached"),
-        };
-        let (x, y) = (xy.0 as isize, xy.1 as isize);
-        for &(dx, dy) in dxdy.iter() {
-            let (xx, yy) = (x + dx, y + dy);
-            if xx < 0 || yy < 0 {
-                continue;
-            }
-            let (xx, yy) = (xx as usize, yy as usize);
-            if let Some(row) = self.tiles.get(yy) {
-                if let Some(t) = row.get(xx) {
-                    if *t != MapTile::Wall {
-                        f(xx, yy);
-                    }
+        let (x, y) = xy;
+        for yy in y.saturating_sub(distance)..(y + distance + 1).min(self.height) {
+            let reach = distance - y.abs_diff(yy);
+            let first = x.saturating_sub(reach);
+            let row = self.tiles[yy].iter().enumerate().skip(first);
+            for (xx, &t) in row.take(x + reach + 1 - first) {
+                if (xx, yy) != xy && t != MapTile::Wall {
+                    f(xx, yy);
                 }
             }
         }
     }
 }
 
-static DISTANCE_L1_2: LazyLock<Vec<(isize, isize)>> = LazyLock::new(|| distance_l1(2));
-static DISTANCE_L1_20: LazyLock<Vec<(isize, isize)>> = LazyLock::new(|| distance_l1(20));
-fn distance_l1(radius: isize) -> Vec<(isize, isize)> {
-    let mut xy = vec![];
-    let radius = radius as isize;
-    for y in -radius..=radius {
-        for x in -radius..=radius {
-            let d = y.abs() + x.abs();
-            if 0 < d && d <= radius {
-                xy.push((x, y));
-            }
-        }
-    }
-    // println!("{radius}\n\t{xy:?}");
-    xy
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Cheat {
+    start: (usize, usize),
+    end: (usize, usize),
+    savings: u32,
 }
 
 //region Iterator over neighboring tiles in a grid; four- or eight-connected.
@@ -296,3 +326,77 @@ impl Iterator for NeighborIterator {
     }
 }
 //endregion
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+###############
+#...#...#.....#
+#.#.#.#.#.###.#
+#S#...#.#.#...#
+#######.#.#.###
+#######.#.#...#
+#######.#.###.#
+###..E#...#...#
+###.#######.###
+#...###...#...#
+#.#####.#.###.#
+#.#...#.#.#...#
+#.#.#.#.#.#.###
+#...#...#...###
+###############
+";
+
+    #[test]
+    fn histograms() {
+        let map = parse_input(EXAMPLE.as_bytes());
+        let (ds, de) = (map.distances_from_start(), map.distances_from_end());
+        let want = BTreeMap::from([
+            (2, 14),
+            (4, 14),
+            (6, 2),
+            (8, 4),
+            (10, 2),
+            (12, 3),
+            (20, 1),
+            (36, 1),
+            (38, 1),
+            (40, 1),
+            (64, 1),
+        ]);
+        assert_eq!(want, map.savings_histogram(&ds, &de, 2, 1));
+        let want = BTreeMap::from([
+            (50, 32),
+            (52, 31),
+            (54, 29),
+            (56, 39),
+            (58, 25),
+            (60, 23),
+            (62, 20),
+            (64, 19),
+            (66, 12),
+            (68, 14),
+            (70, 12),
+            (72, 22),
+            (74, 4),
+            (76, 3),
+        ]);
+        assert_eq!(want, map.savings_histogram(&ds, &de, 20, 50));
+        let mut previous = 0;
+        for radius in 0..25 {
+            let count = map.good_cheats(&ds, &de, radius, 1);
+            assert!(previous <= count);
+            previous = count;
+        }
+        let mut cheats = vec![];
+        map.for_each_cheat(&ds, &de, 2, 64, |c| cheats.push(c));
+        let want = Cheat {
+            start: (7, 7),
+            end: (5, 7),
+            savings: 64,
+        };
+        assert_eq!(vec![want], cheats);
+    }
+}
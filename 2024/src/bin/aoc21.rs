@@ -1,7 +1,9 @@
+use aoc2024::search;
 use std::{
     collections::HashMap,
+    fmt::{Display, Formatter},
     fs::File,
-    io::{BufRead, BufReader},
+    io::{BufRead, BufReader, BufWriter, Write},
     sync::LazyLock,
 };
 fn main() -> std::io::Result<()> {
@@ -10,6 +12,61 @@ fn main() -> std::io::Result<()> {
     // Part one
     let f = File::open(path_input)?;
     let final_codes = parse_input(BufReader::new(f));
+    if let Some(i) = args.iter().position(|a| a == "--keypad") {
+        // rows separated by `/`, e.g. "789/456/123/ 0A"
+        let rows: Vec<&str> = args[i + 1].split('/').collect();
+        let layers: usize = args.get(i + 2).map_or(3, |l| l.parse().expect("layers"));
+        let chain = Chain::new(&Keypad::new(&rows), layers);
+        for code in final_codes.iter() {
+            println!("{code}: {}", chain.presses(code));
+        }
+        return Ok(());
+    }
+    if let Some(i) = args.iter().position(|a| a == "--sequence") {
+        let layers: usize = args[i + 1].parse().expect("layers");
+        let chain = Chain::new(&NUMERIC_KEYPAD, layers);
+        let mut out = BufWriter::new(std::io::stdout().lock());
+        for code in final_codes.iter() {
+            write!(out, "{code}: ")?;
+            for b in chain.stream(code) {
+                out.write_all(&[b])?;
+            }
+            writeln!(out)?;
+        }
+        return Ok(());
+    }
+    if let Some(i) = args.iter().position(|a| a == "--press-at") {
+        let layers: usize = args[i + 1].parse().expect("layers");
+        let n: usize = args[i + 2].parse().expect("index");
+        let chain = Chain::new(&NUMERIC_KEYPAD, layers);
+        for code in final_codes.iter() {
+            let key = chain.press_at(code, n).map(char::from);
+            println!("{code}: {key:?} of {}", chain.presses(code));
+        }
+        return Ok(());
+    }
+    if let Some(i) = args.iter().position(|a| a == "--replay") {
+        let layers: usize = args[i + 1].parse().expect("layers");
+        let mut keypads = vec![&*DIRECTIONAL_KEYPAD; layers];
+        keypads.push(&*NUMERIC_KEYPAD);
+        match replay(&keypads, &args[i + 2]) {
+            Ok(typed) => println!("typed {typed}"),
+            Err(illegal) => println!("{illegal}"),
+        }
+        return Ok(());
+    }
+    if args.iter().any(|a| a == "--print-table") {
+        let chain = Chain::new(&NUMERIC_KEYPAD, 2);
+        chain.check_tables().expect("tables should replay");
+        for (i, level) in chain.levels.iter().enumerate().skip(1) {
+            let mut moves: Vec<_> = level.moves.iter().collect();
+            moves.sort();
+            for ((from, to), m) in moves {
+                println!("{i} {} {} {m}", *from as char, *to as char);
+            }
+        }
+        return Ok(());
+    }
     // Part one
     let p1 = do_part_one(&final_codes, 3);
     dbg!(p1);
@@ -24,10 +81,10 @@ fn parse_input(r: BufReader<File>) -> Vec<String> {
 }
 
 fn do_part_one(goals: &[String], layers: usize) -> usize {
-    let mut cache = HashMap::new();
+    let chain = Chain::new(&NUMERIC_KEYPAD, layers);
     let mut complexities = 0;
     for goal in goals.iter() {
-        let len = shortest_sequence(&mut cache, goal, layers);
+        let len = chain.presses(goal);
         let numeric_part = goal[0..goal.len() - 1].parse::<usize>().unwrap();
         let complexity = len * numeric_part;
         complexities += complexity;
@@ -35,180 +92,419 @@ fn do_part_one(goals: &[String], layers: usize) -> usize {
     complexities
 }
 
-fn shortest_sequence<'goal>(
-    cache: &mut HashMap<(&'goal str, usize), usize>,
-    goal: &'goal str,
-    layer: usize,
-) -> usize {
-    if layer == 0 {
-        return goal.len();
-    }
-    let cache_key = (goal, layer);
-    if let Some(&len) = cache.get(&cache_key) {
-        return len;
-    }
-    // always starts from (and returns to) button `A` on each keypad.
-    let mut len = 0;
-    let from_a = [b'A', goal.as_bytes()[0]];
-    for w in std::iter::once(&from_a[..]).chain(goal.as_bytes().windows(2)) {
-        let from_to = (w[0], w[1]);
-        let subseq = STATE_TRANSITION_TABLE[&from_to].as_str();
-        let rec = shortest_sequence(cache, subseq, layer - 1);
-        len += rec;
-    }
-    cache.insert(cache_key, len);
-    len
-}
-
-/// (from, to) -> "path here"
-static STATE_TRANSITION_TABLE: LazyLock<HashMap<(u8, u8), String>> =
-    LazyLock::new(make_state_transition_table);
-fn make_state_transition_table() -> HashMap<(u8, u8), String> {
-    let mut retval = HashMap::new();
-    retval.insert((b'^', b'^'), "A".to_owned());
-    retval.insert((b'^', b'<'), "v<A".to_owned());
-    retval.insert((b'^', b'>'), "v>A".to_owned());
-    retval.insert((b'^', b'A'), ">A".to_owned());
-    retval.insert((b'^', b'v'), "vA".to_owned());
-    retval.insert((b'<', b'^'), ">^A".to_owned());
-    retval.insert((b'<', b'<'), "A".to_owned());
-    retval.insert((b'<', b'>'), ">>A".to_owned());
-    retval.insert((b'<', b'A'), ">>^A".to_owned());
-    retval.insert((b'<', b'v'), ">A".to_owned());
-    retval.insert((b'>', b'^'), "<^A".to_owned());
-    retval.insert((b'>', b'<'), "<<A".to_owned());
-    retval.insert((b'>', b'>'), "A".to_owned());
-    retval.insert((b'>', b'A'), "^A".to_owned());
-    retval.insert((b'>', b'v'), "<A".to_owned());
-    retval.insert((b'0', b'0'), "A".to_owned());
-    retval.insert((b'0', b'1'), "^<A".to_owned());
-    retval.insert((b'0', b'2'), "^A".to_owned());
-    retval.insert((b'0', b'3'), "^>A".to_owned());
-    retval.insert((b'0', b'4'), "^<^A".to_owned());
-    retval.insert((b'0', b'5'), "^^A".to_owned());
-    retval.insert((b'0', b'6'), "^^>A".to_owned());
-    retval.insert((b'0', b'7'), "^^^<A".to_owned());
-    retval.insert((b'0', b'8'), "^^^A".to_owned());
-    retval.insert((b'0', b'9'), "^^^>A".to_owned());
-    retval.insert((b'0', b'A'), ">A".to_owned());
-    retval.insert((b'1', b'0'), ">vA".to_owned());
-    retval.insert((b'1', b'1'), "A".to_owned());
-    retval.insert((b'1', b'2'), ">A".to_owned());
-    retval.insert((b'1', b'3'), ">>A".to_owned());
-    retval.insert((b'1', b'4'), "^A".to_owned());
-    retval.insert((b'1', b'5'), "^>A".to_owned());
-    retval.insert((b'1', b'6'), "^>>A".to_owned());
-    retval.insert((b'1', b'7'), "^^A".to_owned());
-    retval.insert((b'1', b'8'), "^^>A".to_owned());
-    retval.insert((b'1', b'9'), "^^>>A".to_owned());
-    retval.insert((b'1', b'A'), ">>vA".to_owned());
-    retval.insert((b'2', b'0'), "vA".to_owned());
-    retval.insert((b'2', b'1'), "<A".to_owned());
-    retval.insert((b'2', b'2'), "A".to_owned());
-    retval.insert((b'2', b'3'), ">A".to_owned());
-    retval.insert((b'2', b'4'), "<^A".to_owned());
-    retval.insert((b'2', b'5'), "^A".to_owned());
-    retval.insert((b'2', b'6'), "^>A".to_owned());
-    retval.insert((b'2', b'7'), "<^^A".to_owned());
-    retval.insert((b'2', b'8'), "^^A".to_owned());
-    retval.insert((b'2', b'9'), "^^>A".to_owned());
-    retval.insert((b'2', b'A'), "v>A".to_owned());
-    retval.insert((b'3', b'0'), "<vA".to_owned());
-    retval.insert((b'3', b'1'), "<<A".to_owned());
-    retval.insert((b'3', b'2'), "<A".to_owned());
-    retval.insert((b'3', b'3'), "A".to_owned());
-    retval.insert((b'3', b'4'), "<<^A".to_owned());
-    retval.insert((b'3', b'5'), "<^A".to_owned());
-    retval.insert((b'3', b'6'), "^A".to_owned());
-    retval.insert((b'3', b'7'), "<<^^A".to_owned());
-    retval.insert((b'3', b'8'), "<^^A".to_owned());
-    retval.insert((b'3', b'9'), "^^A".to_owned());
-    retval.insert((b'3', b'A'), "vA".to_owned());
-    retval.insert((b'4', b'0'), ">vvA".to_owned());
-    retval.insert((b'4', b'1'), "vA".to_owned());
-    retval.insert((b'4', b'2'), "v>A".to_owned());
-    retval.insert((b'4', b'3'), "v>>A".to_owned());
-    retval.insert((b'4', b'4'), "A".to_owned());
-    retval.insert((b'4', b'5'), ">A".to_owned());
-    retval.insert((b'4', b'6'), ">>A".to_owned());
-    retval.insert((b'4', b'7'), "^A".to_owned());
-    retval.insert((b'4', b'8'), "^>A".to_owned());
-    retval.insert((b'4', b'9'), "^>>A".to_owned());
-    retval.insert((b'4', b'A'), ">>vvA".to_owned());
-    retval.insert((b'5', b'0'), "vvA".to_owned());
-    retval.insert((b'5', b'1'), "<vA".to_owned());
-    retval.insert((b'5', b'2'), "vA".to_owned());
-    retval.insert((b'5', b'3'), "v>A".to_owned());
-    retval.insert((b'5', b'4'), "<A".to_owned());
-    retval.insert((b'5', b'5'), "A".to_owned());
-    retval.insert((b'5', b'6'), ">A".to_owned());
-    retval.insert((b'5', b'7'), "<^A".to_owned());
-    retval.insert((b'5', b'8'), "^A".to_owned());
-    retval.insert((b'5', b'9'), "^>A".to_owned());
-    retval.insert((b'5', b'A'), "vv>A".to_owned());
-    retval.insert((b'6', b'0'), "<vvA".to_owned());
-    retval.insert((b'6', b'1'), "<<vA".to_owned());
-    retval.insert((b'6', b'2'), "<vA".to_owned());
-    retval.insert((b'6', b'3'), "vA".to_owned());
-    retval.insert((b'6', b'4'), "<<A".to_owned());
-    retval.insert((b'6', b'5'), "<A".to_owned());
-    retval.insert((b'6', b'6'), "A".to_owned());
-    retval.insert((b'6', b'7'), "<<^A".to_owned());
-    retval.insert((b'6', b'8'), "<^A".to_owned());
-    retval.insert((b'6', b'9'), "^A".to_owned());
-    retval.insert((b'6', b'A'), "vvA".to_owned());
-    retval.insert((b'7', b'0'), ">vvvA".to_owned());
-    retval.insert((b'7', b'1'), "vvA".to_owned());
-    retval.insert((b'7', b'2'), "vv>A".to_owned());
-    retval.insert((b'7', b'3'), "vv>>A".to_owned());
-    retval.insert((b'7', b'4'), "vA".to_owned());
-    retval.insert((b'7', b'5'), "v>A".to_owned());
-    retval.insert((b'7', b'6'), "v>>A".to_owned());
-    retval.insert((b'7', b'7'), "A".to_owned());
-    retval.insert((b'7', b'8'), ">A".to_owned());
-    retval.insert((b'7', b'9'), ">>A".to_owned());
-    retval.insert((b'7', b'A'), ">>vvvA".to_owned());
-    retval.insert((b'8', b'0'), "vvvA".to_owned());
-    retval.insert((b'8', b'1'), "<vvA".to_owned());
-    retval.insert((b'8', b'2'), "vvA".to_owned());
-    retval.insert((b'8', b'3'), "vv>A".to_owned());
-    retval.insert((b'8', b'4'), "<vA".to_owned());
-    retval.insert((b'8', b'5'), "vA".to_owned());
-    retval.insert((b'8', b'6'), "v>A".to_owned());
-    retval.insert((b'8', b'7'), "<A".to_owned());
-    retval.insert((b'8', b'8'), "A".to_owned());
-    retval.insert((b'8', b'9'), ">A".to_owned());
-    retval.insert((b'8', b'A'), "vvv>A".to_owned());
-    retval.insert((b'9', b'0'), "<vvvA".to_owned());
-    retval.insert((b'9', b'1'), "<<vvA".to_owned());
-    retval.insert((b'9', b'2'), "<vvA".to_owned());
-    retval.insert((b'9', b'3'), "vvA".to_owned());
-    retval.insert((b'9', b'4'), "<<vA".to_owned());
-    retval.insert((b'9', b'5'), "<vA".to_owned());
-    retval.insert((b'9', b'6'), "vA".to_owned());
-    retval.insert((b'9', b'7'), "<<A".to_owned());
-    retval.insert((b'9', b'8'), "<A".to_owned());
-    retval.insert((b'9', b'9'), "A".to_owned());
-    retval.insert((b'9', b'A'), "vvvA".to_owned());
-    retval.insert((b'A', b'^'), "<A".to_owned());
-    retval.insert((b'A', b'<'), "v<<A".to_owned());
-    retval.insert((b'A', b'>'), "vA".to_owned());
-    retval.insert((b'A', b'0'), "<A".to_owned());
-    retval.insert((b'A', b'1'), "^<<A".to_owned());
-    retval.insert((b'A', b'2'), "<^A".to_owned());
-    retval.insert((b'A', b'3'), "^A".to_owned());
-    retval.insert((b'A', b'4'), "^^<<A".to_owned());
-    retval.insert((b'A', b'5'), "<^^A".to_owned());
-    retval.insert((b'A', b'6'), "^^A".to_owned());
-    retval.insert((b'A', b'7'), "^^^<<A".to_owned());
-    retval.insert((b'A', b'8'), "<^^^A".to_owned());
-    retval.insert((b'A', b'9'), "^^^A".to_owned());
-    retval.insert((b'A', b'A'), "A".to_owned());
-    retval.insert((b'A', b'v'), "<vA".to_owned());
-    retval.insert((b'v', b'^'), "^A".to_owned());
-    retval.insert((b'v', b'<'), "<A".to_owned());
-    retval.insert((b'v', b'>'), ">A".to_owned());
-    retval.insert((b'v', b'A'), "^>A".to_owned());
-    retval.insert((b'v', b'v'), "A".to_owned());
-    retval
+/// Rows top to bottom; a space is a gap no arm may point at.  Every arm
+/// starts on `A`.
+static NUMERIC_KEYPAD: LazyLock<Keypad> =
+    LazyLock::new(|| Keypad::new(&["789", "456", "123", " 0A"]));
+static DIRECTIONAL_KEYPAD: LazyLock<Keypad> = LazyLock::new(|| Keypad::new(&[" ^A", "<v>"]));
+
+#[derive(Clone, Debug)]
+struct Keypad {
+    keys: HashMap<u8, (i32, i32)>,
+    at: HashMap<(i32, i32), u8>,
+}
+
+impl Keypad {
+    fn new(rows: &[&str]) -> Self {
+        let mut keys = HashMap::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, b) in row.bytes().enumerate() {
+                if b != b' ' {
+                    let is_new = keys.insert(b, (x as i32, y as i32)).is_none();
+                    assert!(is_new, "key {} twice", b as char);
+                }
+            }
+        }
+        assert!(keys.contains_key(&b'A'), "arms start on A");
+        let at = keys.iter().map(|(&k, &xy)| (xy, k)).collect();
+        Self { keys, at }
+    }
+
+    /// Where the arm ends up after `direction`, if that is over a key.
+    fn step(&self, from: u8, direction: u8) -> Option<u8> {
+        let (x, y) = self.keys[&from];
+        let xy = match direction {
+            b'^' => (x, y - 1),
+            b'v' => (x, y + 1),
+            b'<' => (x - 1, y),
+            b'>' => (x + 1, y),
+            _ => return None,
+        };
+        self.at.get(&xy).copied()
+    }
+
+    /// Every shortest way of getting the arm from `from` to `to` without
+    /// pointing at a gap, each ending in the press.
+    fn candidate_moves(&self, from: u8, to: u8) -> Vec<String> {
+        let steps = |(x, y): (i32, i32)| {
+            [
+                (b'^', (x, y - 1)),
+                (b'v', (x, y + 1)),
+                (b'<', (x - 1, y)),
+                (b'>', (x + 1, y)),
+            ]
+            .into_iter()
+            .filter(|(_, xy)| self.at.contains_key(xy))
+        };
+        let to_go = search::distances([self.keys[&to]], |&xy| steps(xy).map(|(_, n)| (n, 1)));
+        let mut moves = vec![];
+        let mut todo = vec![(self.keys[&from], String::new())];
+        while let Some((xy, mut walk)) = todo.pop() {
+            let Some(&left) = to_go.get(&xy) else {
+                continue;
+            };
+            if left == 0 {
+                walk.push('A');
+                moves.push(walk);
+                continue;
+            }
+            for (b, next) in steps(xy) {
+                if to_go.get(&next) == Some(&(left - 1)) {
+                    let mut walk = walk.clone();
+                    walk.push(b as char);
+                    todo.push((next, walk));
+                }
+            }
+        }
+        moves.sort();
+        moves
+    }
+}
+
+/// A stack of keypads, each but the first worked by a robot whose arm is
+/// steered from the keypad before it.  The first is pressed directly.
+struct Chain {
+    levels: Vec<Level>,
+}
+
+struct Level {
+    keypad: Keypad,
+    /// (from, to) -> moves for the arm on this keypad, ending with the press
+    moves: HashMap<(u8, u8), String>,
+    /// (from, to) -> presses on the first keypad to make those moves here
+    presses: HashMap<(u8, u8), usize>,
+}
+
+impl Chain {
+    /// `layers` directional keypads, then `keypad`.
+    fn new(keypad: &Keypad, layers: usize) -> Self {
+        let mut keypads = vec![&*DIRECTIONAL_KEYPAD; layers];
+        keypads.push(keypad);
+        Self::from_keypads(&keypads)
+    }
+
+    fn from_keypads(keypads: &[&Keypad]) -> Self {
+        let first = Level {
+            keypad: keypads[0].clone(),
+            moves: HashMap::new(),
+            presses: pairs(keypads[0]).map(|from_to| (from_to, 1)).collect(),
+        };
+        let mut levels = vec![first];
+        for keypad in keypads[1..].iter() {
+            let below = levels.last().expect("first level");
+            let mut moves = HashMap::new();
+            let mut presses = HashMap::new();
+            for (from, to) in pairs(keypad) {
+                let (best, cost) = keypad
+                    .candidate_moves(from, to)
+                    .into_iter()
+                    .map(|m| {
+                        let cost = below.presses_for(&m);
+                        (m, cost)
+                    })
+                    .min_by_key(|(_, cost)| *cost)
+                    .expect("keys should be connected");
+                moves.insert((from, to), best);
+                presses.insert((from, to), cost);
+            }
+            levels.push(Level {
+                keypad: (*keypad).clone(),
+                moves,
+                presses,
+            });
+        }
+        Self { levels }
+    }
+
+    /// Fewest presses on the first keypad that make the last one type `code`.
+    fn presses(&self, code: &str) -> usize {
+        self.levels.last().expect("first level").presses_for(code)
+    }
+
+    /// Ground truth for the move tables: replaying each route on the keypad
+    /// beneath it, starting from `A`, must type exactly the two keys asked
+    /// for.
+    fn check_tables(&self) -> Result<(), IllegalPress> {
+        for pair in self.levels.windows(2) {
+            let [below, here] = pair else { unreachable!() };
+            let keypads = [&below.keypad, &here.keypad];
+            for (&(from, to), m) in here.moves.iter() {
+                let presses = here.moves[&(b'A', from)].clone() + m;
+                let typed = replay(&keypads, &presses)?;
+                let want = String::from_utf8(vec![from, to]).expect("ASCII");
+                assert_eq!(want, typed, "table routes {m} to the wrong key");
+            }
+        }
+        Ok(())
+    }
+
+    /// Which keys those are, one at a time, using memory only for the
+    /// current route on each level.
+    fn stream<'a>(&'a self, code: &'a str) -> Presses<'a> {
+        Presses {
+            chain: self,
+            arms: vec![b'A'; self.levels.len()],
+            stack: vec![(self.levels.len() - 1, code.as_bytes())],
+        }
+    }
+
+    /// Just the `n`th key of the sequence, without writing out the rest.
+    fn press_at(&self, code: &str, mut n: usize) -> Option<u8> {
+        let mut keys = code.as_bytes();
+        let mut level = self.levels.len() - 1;
+        'descend: loop {
+            let mut prev = b'A';
+            for &b in keys {
+                if level == 0 {
+                    if n == 0 {
+                        return Some(b);
+                    }
+                    n -= 1;
+                    continue;
+                }
+                let here = &self.levels[level];
+                let cost = here.presses[&(prev, b)];
+                if n < cost {
+                    keys = here.moves[&(prev, b)].as_bytes();
+                    level -= 1;
+                    continue 'descend;
+                }
+                n -= cost;
+                prev = b;
+            }
+            return None;
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum IllegalPress {
+    /// The first keypad has no such key.
+    UnknownKey { press: usize, key: u8 },
+    /// A robot was sent something other than a direction or `A`.
+    NotACommand { press: usize, level: usize, key: u8 },
+    /// A robot arm would point at a gap, or off the keypad.
+    Gap {
+        press: usize,
+        level: usize,
+        from: u8,
+        direction: u8,
+    },
+}
+
+impl Display for IllegalPress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            IllegalPress::UnknownKey { press, key } => {
+                write!(f, "press {press}: no key {}", key as char)
+            }
+            IllegalPress::NotACommand { press, level, key } => {
+                write!(f, "press {press}: robot {level} sent {}", key as char)
+            }
+            IllegalPress::Gap {
+                press,
+                level,
+                from,
+                direction,
+            } => write!(
+                f,
+                "press {press}: robot {level} moved {} off {}",
+                direction as char, from as char
+            ),
+        }
+    }
+}
+
+/// Presses `presses` on the first of `keypads` and follows each through the
+/// robots, every arm starting on `A`.  Returns whatever the last robot
+/// typed, or the first press that would make a robot misbehave.
+fn replay(keypads: &[&Keypad], presses: &str) -> Result<String, IllegalPress> {
+    let mut arms = vec![b'A'; keypads.len()];
+    let mut typed = String::new();
+    'press: for (press, key) in presses.bytes().enumerate() {
+        if !keypads[0].keys.contains_key(&key) {
+            return Err(IllegalPress::UnknownKey { press, key });
+        }
+        let mut key = key;
+        for (level, keypad) in keypads.iter().enumerate().skip(1) {
+            let from = arms[level];
+            match key {
+                b'A' => key = from,
+                b'^' | b'v' | b'<' | b'>' => {
+                    arms[level] = keypad.step(from, key).ok_or(IllegalPress::Gap {
+                        press,
+                        level,
+                        from,
+                        direction: key,
+                    })?;
+                    continue 'press;
+                }
+                _ => return Err(IllegalPress::NotACommand { press, level, key }),
+            }
+        }
+        typed.push(key as char);
+    }
+    Ok(typed)
+}
+
+/// Keys pressed on the first keypad, expanded lazily level by level.
+struct Presses<'a> {
+    chain: &'a Chain,
+    /// Where the arm on each keypad is pointing.
+    arms: Vec<u8>,
+    /// (level, keys on that level still to press)
+    stack: Vec<(usize, &'a [u8])>,
+}
+
+impl Iterator for Presses<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (level, keys) = self.stack.last_mut()?;
+            let Some((&b, rest)) = keys.split_first() else {
+                self.stack.pop();
+                continue;
+            };
+            *keys = rest;
+            let level = *level;
+            if level == 0 {
+                return Some(b);
+            }
+            let from = std::mem::replace(&mut self.arms[level], b);
+            let moves = &self.chain.levels[level].moves[&(from, b)];
+            self.stack.push((level - 1, moves.as_bytes()));
+        }
+    }
+}
+
+impl Level {
+    /// Arms always start (and end up back) on `A`.
+    fn presses_for(&self, code: &str) -> usize {
+        let mut prev = b'A';
+        let mut total = 0;
+        for b in code.bytes() {
+            total += self.presses[&(prev, b)];
+            prev = b;
+        }
+        total
+    }
+}
+
+fn pairs(keypad: &Keypad) -> impl Iterator<Item = (u8, u8)> + '_ {
+    keypad
+        .keys
+        .keys()
+        .flat_map(|&from| keypad.keys.keys().map(move |&to| (from, to)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let codes: Vec<String> = ["029A", "980A", "179A", "456A", "379A"]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(68, Chain::new(&NUMERIC_KEYPAD, 3).presses("029A"));
+        assert_eq!(126384, do_part_one(&codes, 3));
+        assert_eq!(154115708116294, do_part_one(&codes, 26));
+    }
+
+    #[test]
+    fn gaps() {
+        let keypad = Keypad::new(&["123", " 4 ", "5 A"]);
+        assert_eq!(Vec::<String>::new(), keypad.candidate_moves(b'5', b'1'));
+        let keypad = Keypad::new(&["123", "4 5", "6 A"]);
+        assert_eq!(vec!["^^A".to_owned()], keypad.candidate_moves(b'A', b'3'));
+        // the long way round
+        assert_eq!(
+            vec!["^^>>vA".to_owned()],
+            keypad.candidate_moves(b'6', b'5')
+        );
+        assert_eq!(
+            vec!["<^A".to_owned(), "^<A".to_owned()],
+            NUMERIC_KEYPAD.candidate_moves(b'3', b'5')
+        );
+        assert_eq!(
+            vec!["<^<A".to_owned(), "^<<A".to_owned()],
+            NUMERIC_KEYPAD.candidate_moves(b'A', b'1')
+        );
+    }
+
+    #[test]
+    fn sequences() {
+        let keypads = [&*DIRECTIONAL_KEYPAD, &*DIRECTIONAL_KEYPAD, &*NUMERIC_KEYPAD];
+        let chain = Chain::from_keypads(&keypads);
+        for code in ["029A", "980A", "179A", "456A", "379A"] {
+            let presses = String::from_utf8(chain.stream(code).collect()).unwrap();
+            assert_eq!(chain.presses(code), presses.len());
+            assert_eq!(Ok(code.to_owned()), replay(&keypads, &presses));
+            for (n, b) in presses.bytes().enumerate() {
+                assert_eq!(Some(b), chain.press_at(code, n));
+            }
+            assert_eq!(None, chain.press_at(code, presses.len()));
+        }
+        let chain = Chain::new(&NUMERIC_KEYPAD, 26);
+        let len = chain.presses("029A");
+        let tail: Vec<u8> = (len - 1000..len)
+            .map(|n| chain.press_at("029A", n).unwrap())
+            .collect();
+        assert_eq!(Some(&b'A'), tail.last());
+        let start: Vec<u8> = chain.stream("029A").take(1000).collect();
+        let want: Vec<u8> = (0..1000)
+            .map(|n| chain.press_at("029A", n).unwrap())
+            .collect();
+        assert_eq!(want, start);
+    }
+
+    #[test]
+    fn replays() {
+        let mut keypads = vec![&*DIRECTIONAL_KEYPAD; 3];
+        keypads.push(&*NUMERIC_KEYPAD);
+        let presses = "<vA<AA>>^AvAA<^A>A<v<A>>^AvA^A<vA>^A<v<A>^A>AAvA^A<v<A>A>^AAAvA<^A>A";
+        assert_eq!(Ok("029A".to_owned()), replay(&keypads, presses));
+        assert_eq!(
+            Err(IllegalPress::Gap {
+                press: 3,
+                level: 1,
+                from: b'<',
+                direction: b'<'
+            }),
+            replay(&keypads, "<v<<A")
+        );
+        assert_eq!(
+            Err(IllegalPress::UnknownKey {
+                press: 1,
+                key: b'x'
+            }),
+            replay(&keypads, "<xA")
+        );
+        let numeric_first = [&*NUMERIC_KEYPAD, &*DIRECTIONAL_KEYPAD];
+        assert_eq!(
+            Err(IllegalPress::NotACommand {
+                press: 0,
+                level: 1,
+                key: b'7'
+            }),
+            replay(&numeric_first, "7")
+        );
+        for chain in [
+            Chain::new(&NUMERIC_KEYPAD, 4),
+            Chain::new(&Keypad::new(&["CDEF", "89AB", "4567", "0123"]), 2),
+        ] {
+            assert_eq!(Ok(()), chain.check_tables());
+        }
+    }
 }
@@ -1,5 +1,5 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::BTreeMap,
     fs::File,
     io::{BufRead, BufReader},
 };
@@ -15,10 +15,31 @@ fn main() -> std::io::Result<()> {
     let p1 = do_part_one(&secrets, 2000);
     dbg!(p1);
     // Part two
-    let all_price_changes = get_all_price_changes(&secrets, 2000);
-    // println!("{all_price_changes:?}");
-    let p2 = do_part_two(&all_price_changes);
-    dbg!(p2);
+    let (changes, p2) = do_part_two(&secrets, 2000);
+    dbg!(p2, changes);
+    // Anything else
+    if let Some(i) = args.iter().position(|a| a == "--nth") {
+        let n: u64 = args[i + 1].parse().expect("how far ahead");
+        for &secret in secrets.iter() {
+            println!("{secret}: {}", jump_secret(secret, n));
+        }
+    }
+    if args.iter().any(|a| a == "--prev") {
+        for &secret in secrets.iter() {
+            println!("{secret}: follows {}", prev_secret(secret));
+        }
+    }
+    if args.iter().any(|a| a == "--cycles") {
+        for &secret in secrets.iter() {
+            match cycle_length(secret) {
+                Some(length) => println!("{secret}: cycle of {length}"),
+                None => println!("{secret}: never comes back, being over 24 bits"),
+            }
+        }
+        for (length, count) in all_cycle_lengths() {
+            println!("{count} cycles of length {length}");
+        }
+    }
     Ok(())
 }
 
@@ -48,6 +69,132 @@ const fn next_secret(secret: u64) -> u64 {
     secret
 }
 
+/// Undoes `next_secret`, which is a bijection on 24 bits: each step xors in
+/// a shifted copy of itself, so the bits it reads from can be recovered in
+/// order, one shift's worth at a time.
+const fn prev_secret(secret: u64) -> u64 {
+    let mut secret = secret;
+    secret = unshift_left(secret, 11);
+    secret = unshift_right(secret, 5);
+    secret = unshift_left(secret, 6);
+    secret
+}
+
+const fn unshift_left(mixed: u64, shift: u32) -> u64 {
+    let mut secret = mixed;
+    let mut known = shift;
+    while known < 24 {
+        secret = prune(mixed ^ (secret << shift));
+        known += shift;
+    }
+    secret
+}
+
+const fn unshift_right(mixed: u64, shift: u32) -> u64 {
+    let mut secret = mixed;
+    let mut known = shift;
+    while known < 24 {
+        secret = mixed ^ (secret >> shift);
+        known += shift;
+    }
+    secret
+}
+
+/// `next_secret` is linear over GF(2), so it is a 24x24 bit matrix; powers
+/// of it jump ahead.  Column `i` is where bit `i` of the secret ends up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Gf2Matrix([u32; 24]);
+
+impl Gf2Matrix {
+    const IDENTITY: Self = {
+        let mut columns = [0; 24];
+        let mut i = 0;
+        while i < 24 {
+            columns[i] = 1 << i;
+            i += 1;
+        }
+        Self(columns)
+    };
+
+    const NEXT_SECRET: Self = {
+        let mut columns = [0; 24];
+        let mut i = 0;
+        while i < 24 {
+            columns[i] = next_secret(1 << i) as u32;
+            i += 1;
+        }
+        Self(columns)
+    };
+
+    fn apply(&self, secret: u64) -> u64 {
+        let mut out = 0;
+        for (i, column) in self.0.iter().enumerate() {
+            if secret >> i & 1 == 1 {
+                out ^= column;
+            }
+        }
+        out as u64
+    }
+
+    /// `self` after `first`.
+    fn after(&self, first: &Self) -> Self {
+        Self(first.0.map(|column| self.apply(column as u64) as u32))
+    }
+
+    fn pow(&self, mut n: u64) -> Self {
+        let mut result = Self::IDENTITY;
+        let mut square = *self;
+        while n > 0 {
+            if n & 1 == 1 {
+                result = result.after(&square);
+            }
+            square = square.after(&square);
+            n >>= 1;
+        }
+        result
+    }
+}
+
+/// The `n`th secret after `secret`, in O(log n).
+fn jump_secret(secret: u64, n: u64) -> u64 {
+    Gf2Matrix::NEXT_SECRET.pow(n).apply(secret)
+}
+
+/// How many steps until `secret` comes back round.  Only 24-bit secrets ever
+/// do, since every step prunes to 24 bits; anything wider is `None`.
+fn cycle_length(secret: u64) -> Option<u64> {
+    if prune(secret) != secret {
+        return None;
+    }
+    let mut s = next_secret(secret);
+    let mut length = 1;
+    while s != secret {
+        s = next_secret(s);
+        length += 1;
+    }
+    Some(length)
+}
+
+/// Cycle length -> how many cycles of that length cover all 2^24 secrets.
+fn all_cycle_lengths() -> BTreeMap<u64, u64> {
+    let mut seen = vec![false; 1 << 24];
+    let mut lengths = BTreeMap::new();
+    for start in 0..1 << 24 {
+        if seen[start as usize] {
+            continue;
+        }
+        let mut s = start;
+        let mut length = 0;
+        while !seen[s as usize] {
+            seen[s as usize] = true;
+            s = next_secret(s);
+            length += 1;
+        }
+        *lengths.entry(length).or_default() += 1;
+    }
+    lengths
+}
+
 fn do_part_one(secrets: &[u64], iterations: usize) -> u64 {
     secrets
         .iter()
@@ -62,48 +209,88 @@ fn do_part_one(secrets: &[u64], iterations: usize) -> u64 {
         .sum()
 }
 
-fn get_all_price_changes(secrets: &[u64], iterations: usize) -> Vec<Vec<(u8, i8)>> {
-    secrets
-        .iter()
-        .map(|secret| {
-            let mut price_changes = Vec::with_capacity(iterations);
-            let mut s = *secret;
-            let mut previous_price = (*secret % 10) as i8;
-            for _ in 0..iterations {
-                s = next_secret(s);
-                let price = (s % 10) as i8;
-                let change = price - previous_price;
-                price_changes.push((price as u8, change));
-                previous_price = price;
-            }
-            price_changes
-        })
-        .collect()
+/// Four price changes, each in `-9..=9`, as a base-19 number.
+const PATTERNS: usize = 19 * 19 * 19 * 19;
+
+fn decode_pattern(mut key: usize) -> [i8; 4] {
+    let mut changes = [0; 4];
+    for change in changes.iter_mut().rev() {
+        *change = (key % 19) as i8 - 9;
+        key /= 19;
+    }
+    changes
 }
 
-fn do_part_two(all_price_changes: &Vec<Vec<(u8, i8)>>) -> u64 {
-    let mut max_per_pattern: HashMap<u32, u64> = HashMap::new();
-    for price_changes in all_price_changes.iter() {
-        let mut seen_first: HashSet<u32> = HashSet::new();
-        for pattern in price_changes.windows(4) {
-            let price = pattern[3].0;
-            let key = {
-                let mut key = 0_u32;
-                for (_price, change) in pattern.iter().copied() {
-                    let change_bits = change.to_ne_bytes()[0] as u32;
-                    key = (key << 8) | change_bits;
-                }
-                key
-            };
-            if !seen_first.insert(key) {
-                continue;
+/// The four price changes to wait for that earn the most bananas, and how
+/// many that is.  Each buyer only counts the first time the pattern shows.
+fn do_part_two(secrets: &[u64], iterations: usize) -> ([i8; 4], u64) {
+    let mut bananas = vec![0_u64; PATTERNS];
+    // which buyer last saw each pattern, so it never needs clearing
+    let mut seen_by = vec![usize::MAX; PATTERNS];
+    for (buyer, &secret) in secrets.iter().enumerate() {
+        let mut s = secret;
+        let mut previous_price = (s % 10) as i8;
+        let mut key = 0;
+        for i in 0..iterations {
+            s = next_secret(s);
+            let price = (s % 10) as i8;
+            let change = price - previous_price;
+            previous_price = price;
+            key = (key * 19 + (change + 9) as usize) % PATTERNS;
+            if i >= 3 && seen_by[key] != buyer {
+                seen_by[key] = buyer;
+                bananas[key] += price as u64;
             }
-            *max_per_pattern.entry(key).or_default() += price as u64;
         }
     }
-    max_per_pattern
-        .values()
-        .copied()
-        .max()
-        .expect("should have non-empty prices")
+    let (key, &most) = bananas
+        .iter()
+        .enumerate()
+        .rev()
+        .max_by_key(|&(_, b)| b)
+        .expect("should have non-empty prices");
+    (decode_pattern(key), most)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inverse() {
+        for secret in 0..1 << 24 {
+            assert_eq!(secret, prev_secret(next_secret(secret)));
+        }
+    }
+
+    #[test]
+    fn jump_ahead() {
+        let mut s = 123;
+        for n in 0..3000 {
+            assert_eq!(s, jump_secret(123, n));
+            s = next_secret(s);
+        }
+        assert_eq!(8685429, jump_secret(1, 2000));
+        let length = cycle_length(123).unwrap();
+        assert_eq!(123, jump_secret(123, length));
+        assert_eq!(None, cycle_length(1 << 24));
+        assert_eq!(
+            jump_secret(123, 10),
+            jump_secret(123, length * 1_000_000 + 10)
+        );
+        let lengths = all_cycle_lengths();
+        assert_eq!(1 << 24, lengths.iter().map(|(l, c)| l * c).sum::<u64>());
+        assert_eq!(Some(&1), lengths.get(&1), "zero stays zero");
+        assert!(lengths.contains_key(&length));
+    }
+
+    #[test]
+    fn best_changes() {
+        assert_eq!(([-2, 1, -1, 3], 23), do_part_two(&[1, 2, 3, 2024], 2000));
+        for key in [0, 1, 18, 19, PATTERNS - 1] {
+            let changes = decode_pattern(key);
+            let back = changes.iter().fold(0, |k, &c| k * 19 + (c + 9) as usize);
+            assert_eq!(key, back);
+        }
+    }
 }
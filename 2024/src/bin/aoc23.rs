@@ -1,5 +1,7 @@
+use aoc2024::{clique, dot::Graph};
 use std::{
-    collections::HashSet,
+    borrow::Cow,
+    collections::{HashMap, HashSet},
     fs::File,
     io::{BufRead, BufReader},
 };
@@ -10,28 +12,135 @@ fn main() -> std::io::Result<()> {
     let path_input = args.get(1).expect("Should have an input file");
     // Part one
     let f = File::open(path_input)?;
-    let connections = parse_input(BufReader::new(f));
-    let adj = make_graph_adj(connections.iter().copied(), 26 * 26);
+    let (machines, connections) = parse_input(BufReader::new(f));
+    let adj = make_graph_adj(connections.iter().copied(), machines.id_limit());
     // Part one
-    let p1 = do_part_one(&connections, &adj);
+    let p1 = do_part_one(&machines, &adj);
     dbg!(p1);
+    // e.g. `--cliques 4 q` for every 4-clique with a machine starting with `q`
+    if let Some(i) = args.iter().position(|a| a == "--cliques") {
+        let k: usize = args[i + 1].parse().expect("clique size");
+        let prefix = &args[i + 2];
+        let mut count = 0_u64;
+        clique::for_each_k_clique(
+            &adj,
+            k,
+            |id| machines.starts_with(id, prefix),
+            |members| {
+                count += 1;
+                println!("{}", machines.join(members));
+            },
+        );
+        dbg!(count);
+    }
     // Part two
-    let p2 = do_part_two(&adj);
+    let lan = find_largest_lan(&adj);
+    let p2 = do_part_two(&machines, &lan);
     dbg!(p2);
+    // e.g. `--emit-dot aoc23.dot`; a file of its own, since `--cliques` prints too
+    if let Some(i) = args.iter().position(|a| a == "--emit-dot") {
+        let path = args.get(i + 1).expect("a file to write the DOT to");
+        std::fs::write(
+            path,
+            generate_graphviz(&machines, &connections, &lan).to_string(),
+        )?;
+    }
     Ok(())
 }
 
-fn parse_input(r: BufReader<File>) -> HashSet<(usize, usize)> {
-    r.lines()
+/// Names are whatever is on either side of the last `-`, so only the first
+/// one may contain any.
+fn parse_input(r: impl BufRead) -> (MachineTable, HashSet<(usize, usize)>) {
+    let pairs: Vec<(String, String)> = r
+        .lines()
         .flat_map(|l| l.ok().into_iter())
         .map(|line| {
-            let mut iter = line.split('-');
-            let left = to_machine_id(iter.next().expect("sane input"));
-            let right = to_machine_id(iter.next().expect("sane input"));
+            let (left, right) = line.rsplit_once('-').expect("sane input");
+            (left.to_owned(), right.to_owned())
+        })
+        .collect();
+    let machines = MachineTable::new(pairs.iter().flat_map(|(l, r)| [l.as_str(), r.as_str()]));
+    let connections = pairs
+        .iter()
+        .map(|(left, right)| {
+            let left = machines.id(left);
+            let right = machines.id(right);
             // Connections are symmetric, so why worry about ordering?
             (left.min(right), left.max(right))
         })
-        .collect()
+        .collect();
+    (machines, connections)
+}
+
+/// Machine names <-> ids in `0..id_limit()`.  Either way, sorting by id sorts
+/// by name.
+enum MachineTable {
+    /// Every name is two lowercase ASCII letters, as in the puzzle, so ids are
+    /// just those letters in base 26.
+    TwoLetters,
+    /// Anything else, such as real hostnames.
+    Interned {
+        ids: HashMap<String, usize>,
+        names: Vec<String>,
+    },
+}
+
+impl MachineTable {
+    fn new<'a>(names: impl Iterator<Item = &'a str>) -> Self {
+        let mut names: Vec<_> = names.collect();
+        let is_two_letters =
+            |name: &&str| name.len() == 2 && name.bytes().all(|b| b.is_ascii_lowercase());
+        if names.iter().all(is_two_letters) {
+            return MachineTable::TwoLetters;
+        }
+        names.sort_unstable();
+        names.dedup();
+        let names: Vec<String> = names.into_iter().map(|n| n.to_owned()).collect();
+        let ids = names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (name.clone(), id))
+            .collect();
+        MachineTable::Interned { ids, names }
+    }
+
+    /// Every id is below this.  With [`MachineTable::TwoLetters`] most of
+    /// them name no machine, and so have no connections either.
+    fn id_limit(&self) -> usize {
+        match self {
+            MachineTable::TwoLetters => 26 * 26,
+            MachineTable::Interned { names, .. } => names.len(),
+        }
+    }
+
+    fn id(&self, name: &str) -> usize {
+        match self {
+            MachineTable::TwoLetters => to_machine_id(name),
+            MachineTable::Interned { ids, .. } => ids[name],
+        }
+    }
+
+    fn name(&self, id: usize) -> Cow<'_, str> {
+        match self {
+            MachineTable::TwoLetters => {
+                Cow::Owned(String::from_utf8_lossy(&from_machine_id(id)).into_owned())
+            }
+            MachineTable::Interned { names, .. } => Cow::Borrowed(&names[id]),
+        }
+    }
+
+    fn starts_with(&self, id: usize, prefix: &str) -> bool {
+        match self {
+            MachineTable::TwoLetters => from_machine_id(id).starts_with(prefix.as_bytes()),
+            MachineTable::Interned { names, .. } => names[id].starts_with(prefix),
+        }
+    }
+
+    /// Comma-separated names.
+    fn join(&self, ids: &[usize]) -> String {
+        let names: Vec<_> = ids.iter().map(|&id| self.name(id)).collect();
+        names.join(",")
+    }
 }
 
 /// Turns input IDs into `0..26^2` -range machine ID.
@@ -46,12 +155,6 @@ const fn from_machine_id(id: usize) -> [u8; 2] {
     [car, cdr]
 }
 
-const START_T: usize = (b't' - b'a') as usize * 26;
-const STOP_T_EXC: usize = START_T + 26;
-const fn machine_id_starts_with_t(id: usize) -> bool {
-    START_T <= id && id < STOP_T_EXC
-}
-
 fn make_graph_adj(edges: impl Iterator<Item = (usize, usize)>, n: usize) -> Vec<Vec<usize>> {
     let mut retval = vec![vec![]; n];
     for (a, b) in edges {
@@ -64,69 +167,134 @@ fn make_graph_adj(edges: impl Iterator<Item = (usize, usize)>, n: usize) -> Vec<
     retval
 }
 
-fn do_part_one(connections: &HashSet<(usize, usize)>, adj: &Vec<Vec<usize>>) -> u64 {
-    let mut triangles = HashSet::new();
-    for (from, to) in adj.iter().enumerate() {
-        if to.is_empty() {
-            continue;
-        }
-        // Hashes are slow and 26^3 is not very many.
-        for (a, b) in connections.iter().copied() {
-            // from always starts with `t`
-            if !(machine_id_starts_with_t(from)
-                || machine_id_starts_with_t(a)
-                || machine_id_starts_with_t(b))
-            {
-                continue;
-            }
-            if adj[a].binary_search(&from).is_err() {
-                continue;
-            }
-            if adj[b].binary_search(&from).is_err() {
-                continue;
-            }
-            let mut tri = [from, a, b];
-            tri.sort_unstable();
-            triangles.insert(tri);
+/// Triangles with at least one machine whose name starts with `t`.
+fn do_part_one(machines: &MachineTable, adj: &[Vec<usize>]) -> u64 {
+    clique::count_k_cliques(adj, 3, |id| machines.starts_with(id, "t"))
+}
+
+/// The largest fully-connected set; see [`clique::maximum_clique`].
+fn find_largest_lan(adj: &[Vec<usize>]) -> Vec<usize> {
+    clique::maximum_clique(adj)
+}
+
+/// LAN members -> password
+fn do_part_two(machines: &MachineTable, lan: &[usize]) -> String {
+    machines.join(lan)
+}
+
+/// The LAN party gets a box of its own, and its connections are drawn in red.
+fn generate_graphviz(
+    machines: &MachineTable,
+    connections: &HashSet<(usize, usize)>,
+    lan: &[usize],
+) -> Graph {
+    let mut gv = Graph::undirected("aoc23");
+    let mut party = Graph::subgraph("cluster_lan_party");
+    party
+        .attr("label", "LAN party")
+        .node_attr("style", "filled")
+        .node_attr("fillcolor", "gold");
+    for &id in lan {
+        party.node(machines.name(id));
+    }
+    gv.add_subgraph(party);
+    let mut edges: Vec<_> = connections.iter().copied().collect();
+    edges.sort_unstable();
+    for (a, b) in edges {
+        let edge = gv.edge(machines.name(a), machines.name(b));
+        if lan.contains(&a) && lan.contains(&b) {
+            edge.attr("color", "red").attr("penwidth", 2);
         }
     }
-    triangles.len() as u64
+    gv
 }
 
-/// Brute force; when built with `--release`, still takes under 100ms, including
-/// the overhead of re-doing Part One and invoking Cargo.
-fn do_part_two(adj: &Vec<Vec<usize>>) -> String {
-    let mut fully_connected = HashSet::new();
-    // The largest LAN must be contained within adj[this_one] = [and, some, of, these].
-    // If it were split across multiple adj[i], then it would not be fully-connected.
-    for (from1, to) in adj.iter().enumerate() {
-        for to_me in 0..to.len() {
-            let from2 = to[to_me];
-            let mut maybe = HashSet::new();
-            maybe.insert(from1);
-            maybe.insert(from2);
-            for from3 in to[to_me + 1..].iter().copied() {
-                let still_fully_connected = maybe
-                    .iter()
-                    .all(|&id| adj[id].binary_search(&from3).is_ok());
-                if still_fully_connected {
-                    maybe.insert(from3);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "kh-tc
+qp-kh
+de-cg
+ka-co
+yn-aq
+qp-ub
+cg-tb
+vc-aq
+tb-ka
+wh-tc
+yn-cg
+kh-ub
+ta-co
+de-co
+tc-td
+tb-wq
+wh-td
+ta-ka
+td-qp
+aq-cg
+wq-ub
+ub-vc
+de-ta
+wq-aq
+wq-vc
+wh-yn
+ka-de
+kh-ta
+co-tc
+wh-qp
+tb-vc
+td-yn
+";
+
+    fn solve(input: &str) -> (MachineTable, Vec<Vec<usize>>) {
+        let (machines, connections) = parse_input(input.as_bytes());
+        let adj = make_graph_adj(connections.into_iter(), machines.id_limit());
+        (machines, adj)
+    }
+
+    #[test]
+    fn example() {
+        let (machines, adj) = solve(EXAMPLE);
+        assert_eq!(7, do_part_one(&machines, &adj));
+        assert_eq!(
+            "co,de,ka,ta",
+            do_part_two(&machines, &find_largest_lan(&adj))
+        );
+        // only machines that turned up, not every two-letter id
+        let q = clique::k_cliques(&adj, 1, |id| machines.starts_with(id, "q"));
+        assert_eq!(
+            vec!["qp"],
+            q.iter().map(|c| machines.join(c)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn hostnames() {
+        let rename = |name| match name {
+            "ka" => "web-1",
+            "kh" => "db-2",
+            name => name,
+        };
+        let input: String = EXAMPLE
+            .lines()
+            .map(|line| {
+                let (left, right) = line.split_once('-').unwrap();
+                let (left, right) = (rename(left), rename(right));
+                // a hyphenated name goes first
+                if right.contains('-') {
+                    format!("{right}-{left}\n")
+                } else {
+                    format!("{left}-{right}\n")
                 }
-            }
-            if maybe.len() > fully_connected.len() {
-                fully_connected = maybe;
-            }
-        }
+            })
+            .collect();
+        let (machines, adj) = solve(&input);
+        assert!(matches!(machines, MachineTable::Interned { .. }));
+        assert_eq!(7, do_part_one(&machines, &adj));
+        assert_eq!(
+            "co,de,ta,web-1",
+            do_part_two(&machines, &find_largest_lan(&adj))
+        );
     }
-    // LAN members -> password
-    let mut buf: Vec<_> = fully_connected.into_iter().collect();
-    buf.sort_unstable();
-    let mut retval: String = buf
-        .into_iter()
-        // shame that .intersperse() is still limited to nightly
-        .flat_map(|id| from_machine_id(id).into_iter().chain(std::iter::once(b',')))
-        .map(|b| b as char)
-        .collect();
-    let _trailing_comma = retval.pop();
-    retval
 }
@@ -1,29 +1,69 @@
+use aoc2024::{dot::Graph, rng::XorShift64};
 use std::{
     collections::HashMap,
+    fmt::{Display, Formatter, Write},
     fs::File,
     io::{BufRead, BufReader},
+    str::FromStr,
 };
 
 /// Remember to build with `--release`!
 fn main() -> std::io::Result<()> {
     let args: Vec<String> = std::env::args().collect();
     let path_input = args.get(1).expect("Should have an input file");
+    // Netlists exported from other tools have no initial values, so no part one.
+    let (inputs, netlist) = if path_input.ends_with(".v") {
+        let v = std::fs::read_to_string(path_input)?;
+        (None, Netlist::from_verilog(&v).expect("sane Verilog"))
+    } else if path_input.ends_with(".blif") {
+        let b = std::fs::read_to_string(path_input)?;
+        (None, Netlist::from_blif(&b).expect("sane BLIF"))
+    } else {
+        let f = File::open(path_input)?;
+        let (inputs, netlist) = parse_input(BufReader::new(f));
+        (Some(inputs), netlist)
+    };
+    if args.iter().any(|a| a == "--emit-verilog") {
+        print!("{}", netlist.to_verilog());
+        return Ok(());
+    }
+    if args.iter().any(|a| a == "--emit-blif") {
+        print!("{}", netlist.to_blif());
+        return Ok(());
+    }
+    let gates = &netlist.gates;
+    let input_length = netlist
+        .inputs
+        .iter()
+        .filter(|id| id.starts_with('x'))
+        .count() as u32;
+    // e.g. `--emit-dot aoc24.dot`
+    if let Some(i) = args.iter().position(|a| a == "--emit-dot") {
+        let path = args.get(i + 1).expect("a file to write the DOT to");
+        std::fs::write(path, generate_graphviz(gates, input_length).to_string())?;
+        return Ok(());
+    }
+    let is_puzzle = inputs.is_some();
     // Part one
-    let f = File::open(path_input)?;
-    let (inputs, gates) = parse_input(BufReader::new(f));
-    // Part one
-    let p1 = do_part_one(inputs, &gates);
-    println!("p1 = {p1:#010b}");
-    dbg!(p1);
-    // Part two; brute force was too slow, so did it visually with graphviz instead.
-    // let p2 = do_part_two(gates);
-    // dbg!(p2);
-    let gv = generate_graphviz(&gates, 46);
-    println!("{gv}");
+    if let Some(inputs) = inputs {
+        let p1 = do_part_one(inputs, gates);
+        println!("p1 = {p1:#010b}");
+        dbg!(p1);
+    }
+    // Part two; brute force was too slow, so did it visually with graphviz at first.
+    let suspects = find_miswired_outputs(gates, input_length);
+    for m in suspects.iter() {
+        println!("suspect {} ({}): {}", m.out, m.op, m.reason);
+    }
+    // Only the puzzle promises a repairable adder with exactly four swaps.
+    if is_puzzle {
+        let p2 = do_part_two(gates, input_length, &suspects);
+        dbg!(p2);
+    }
     Ok(())
 }
 
-fn parse_input(r: BufReader<File>) -> (HashMap<String, bool>, Vec<[String; 4]>) {
+fn parse_input(r: BufReader<File>) -> (HashMap<String, bool>, Netlist) {
     let mut inputs = HashMap::new();
     let mut gates = vec![];
     let mut have_reached_gates = false;
@@ -34,12 +74,13 @@ fn parse_input(r: BufReader<File>) -> (HashMap<String, bool>, Vec<[String; 4]>)
         }
         if have_reached_gates {
             let mut iter_parts = line.split(" ");
-            let id0 = iter_parts.next().expect("sane input").to_owned();
-            let op = iter_parts.next().expect("sane input").to_owned();
-            let id1 = iter_parts.next().expect("sane input").to_owned();
-            let _arrow = iter_parts.next().expect("sane input").to_owned();
-            let id2 = iter_parts.next().expect("sane input").to_owned();
-            gates.push([id0, op, id1, id2]);
+            let id0 = iter_parts.next().expect("sane input");
+            let op = iter_parts.next().expect("sane input");
+            let id1 = iter_parts.next().expect("sane input");
+            let _arrow = iter_parts.next().expect("sane input");
+            let id2 = iter_parts.next().expect("sane input");
+            let op = op.parse().expect("sane input");
+            gates.push(Gate::new(op, id0, id1, id2));
         } else {
             let mut iter_parts = line.split(": ");
             let id = iter_parts.next().expect("sane input").to_owned();
@@ -52,168 +93,1027 @@ fn parse_input(r: BufReader<File>) -> (HashMap<String, bool>, Vec<[String; 4]>)
             inputs.insert(id, init);
         }
     }
-    (inputs, gates)
+    let mut input_ids: Vec<_> = inputs.keys().cloned().collect();
+    input_ids.sort_unstable();
+    let mut outputs: Vec<_> = gates
+        .iter()
+        .map(|g: &Gate| g.out.clone())
+        .filter(|id| id.starts_with('z'))
+        .collect();
+    outputs.sort_unstable();
+    let netlist = Netlist {
+        name: "aoc24".to_owned(),
+        inputs: input_ids,
+        outputs,
+        gates,
+    };
+    (inputs, netlist)
+}
+
+fn do_part_one(inputs: HashMap<String, bool>, gates: &[Gate]) -> u64 {
+    let netlist = CompiledNetlist::compile(gates, inputs.keys()).expect("well-formed netlist");
+    let mut values = netlist.blank_values();
+    for (id, val) in inputs.iter() {
+        values[netlist.ids[id]] = *val as u64;
+    }
+    netlist.evaluate(&mut values);
+    // Only lane 0 is in use.
+    netlist.read_bus(&values, 'z')[0]
+}
+
+//region Typed gate-level netlist, with structural Verilog and BLIF import/export.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum GateOp {
+    And,
+    Or,
+    Xor,
+    Nand,
+    Nor,
+    Not,
+    /// Picks `b` where `sel` is high, else `a`.
+    Mux,
+}
+
+impl GateOp {
+    const ALL: [GateOp; 7] = [
+        GateOp::And,
+        GateOp::Or,
+        GateOp::Xor,
+        GateOp::Nand,
+        GateOp::Nor,
+        GateOp::Not,
+        GateOp::Mux,
+    ];
+
+    const fn arity(self) -> usize {
+        match self {
+            GateOp::Not => 1,
+            GateOp::Mux => 3,
+            _ => 2,
+        }
+    }
+
+    /// Bitwise, so every lane is independent.  `Not` ignores `b`, and only
+    /// `Mux` reads `sel`.
+    const fn apply(self, a: u64, b: u64, sel: u64) -> u64 {
+        match self {
+            GateOp::And => a & b,
+            GateOp::Or => a | b,
+            GateOp::Xor => a ^ b,
+            GateOp::Nand => !(a & b),
+            GateOp::Nor => !(a | b),
+            GateOp::Not => !a,
+            GateOp::Mux => (sel & b) | (!sel & a),
+        }
+    }
+
+    /// Gate primitive, if Verilog has one.
+    const fn verilog_primitive(self) -> Option<&'static str> {
+        match self {
+            GateOp::And => Some("and"),
+            GateOp::Or => Some("or"),
+            GateOp::Xor => Some("xor"),
+            GateOp::Nand => Some("nand"),
+            GateOp::Nor => Some("nor"),
+            GateOp::Not => Some("not"),
+            GateOp::Mux => None,
+        }
+    }
+
+    /// On-set rows of the `.names` table, with inputs in [`Gate::inputs`] order.
+    const fn blif_cover(self) -> &'static [&'static str] {
+        match self {
+            GateOp::And => &["11 1"],
+            GateOp::Or => &["1- 1", "-1 1"],
+            GateOp::Xor => &["10 1", "01 1"],
+            GateOp::Nand => &["0- 1", "-0 1"],
+            GateOp::Nor => &["00 1"],
+            GateOp::Not => &["0 1"],
+            GateOp::Mux => &["01- 1", "1-1 1"],
+        }
+    }
+}
+
+impl Display for GateOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            GateOp::And => "AND",
+            GateOp::Or => "OR",
+            GateOp::Xor => "XOR",
+            GateOp::Nand => "NAND",
+            GateOp::Nor => "NOR",
+            GateOp::Not => "NOT",
+            GateOp::Mux => "MUX",
+        })
+    }
+}
+
+impl FromStr for GateOp {
+    type Err = NetlistError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        GateOp::ALL
+            .into_iter()
+            .find(|op| op.to_string() == s)
+            .ok_or_else(|| NetlistError::UnknownOperator(s.to_owned()))
+    }
 }
 
-fn do_part_one(mut state: HashMap<String, bool>, gates: &[[String; 4]]) -> u64 {
-    simulate(&mut state, gates);
-    into_zulu(state)
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Gate {
+    op: GateOp,
+    a: String,
+    /// Same as `a` for `Not`.
+    b: String,
+    /// Only for `Mux`.
+    sel: Option<String>,
+    out: String,
 }
 
-fn simulate(state: &mut HashMap<String, bool>, gates: &[[String; 4]]) {
-    let mut active = gates.to_vec();
-    let mut future = vec![];
-    while !active.is_empty() {
-        // println!("Remaining: {}", active.len());
-        for [i0, op, i1, out] in active.drain(..) {
-            if let Some(&ii00) = state.get(&i0) {
-                if let Some(&ii11) = state.get(&i1) {
-                    let outout = match op.as_str() {
-                        "AND" => ii00 & ii11,
-                        "OR" => ii00 | ii11,
-                        "XOR" => ii00 ^ ii11,
-                        _ => panic!("bad input"),
-                    };
-                    state.insert(out, outout);
-                    continue;
+impl Gate {
+    fn new(op: GateOp, a: &str, b: &str, out: &str) -> Self {
+        Self {
+            op,
+            a: a.to_owned(),
+            b: b.to_owned(),
+            sel: None,
+            out: out.to_owned(),
+        }
+    }
+
+    fn mux(sel: &str, a: &str, b: &str, out: &str) -> Self {
+        Self {
+            sel: Some(sel.to_owned()),
+            ..Self::new(GateOp::Mux, a, b, out)
+        }
+    }
+
+    /// `[a]` for `Not`, `[sel, a, b]` for `Mux`, and `[a, b]` for the rest.
+    fn inputs(&self) -> Vec<&String> {
+        match (self.op, &self.sel) {
+            (GateOp::Not, _) => vec![&self.a],
+            (GateOp::Mux, Some(sel)) => vec![sel, &self.a, &self.b],
+            (GateOp::Mux, None) => panic!("MUX without a select line"),
+            _ => vec![&self.a, &self.b],
+        }
+    }
+
+    /// Inverse of [`Self::inputs`].
+    fn from_inputs(op: GateOp, inputs: &[&str], out: &str) -> Self {
+        match (op, inputs) {
+            (GateOp::Not, [a]) => Gate::new(op, a, a, out),
+            (GateOp::Mux, [sel, a, b]) => Gate::mux(sel, a, b, out),
+            (_, [a, b]) => Gate::new(op, a, b, out),
+            _ => panic!("wrong number of inputs for {op}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct Netlist {
+    name: String,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    gates: Vec<Gate>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct NetlistParseError(String);
+
+impl Netlist {
+    /// Gate outputs that are not also module outputs.
+    fn internal_wires(&self) -> impl Iterator<Item = &String> {
+        self.gates
+            .iter()
+            .map(|g| &g.out)
+            .filter(|out| !self.outputs.contains(out))
+    }
+
+    /// Primitives where Verilog has them, `assign` for the rest.  Instance names
+    /// are made up on the way out and dropped on the way back in.
+    fn to_verilog(&self) -> String {
+        let mut v = String::new();
+        let ports: Vec<_> = self.inputs.iter().chain(self.outputs.iter()).collect();
+        let join = |wires: Vec<&String>| {
+            wires
+                .into_iter()
+                .map(|w| w.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        writeln!(v, "module {} ({});", self.name, join(ports)).unwrap();
+        writeln!(v, "  input {};", join(self.inputs.iter().collect())).unwrap();
+        writeln!(v, "  output {};", join(self.outputs.iter().collect())).unwrap();
+        let wires: Vec<_> = self.internal_wires().collect();
+        if !wires.is_empty() {
+            writeln!(v, "  wire {};", join(wires)).unwrap();
+        }
+        for (i, gate) in self.gates.iter().enumerate() {
+            let ins = join(gate.inputs());
+            match gate.op.verilog_primitive() {
+                Some(prim) => writeln!(v, "  {prim} g{i} ({}, {ins});", gate.out),
+                None => {
+                    let ins = gate.inputs();
+                    writeln!(
+                        v,
+                        "  assign {} = {} ? {} : {};",
+                        gate.out, ins[0], ins[2], ins[1]
+                    )
+                }
+            }
+            .unwrap();
+        }
+        v.push_str("endmodule\n");
+        v
+    }
+
+    /// Reads back what [`Self::to_verilog`] writes, as well as the equivalent
+    /// `assign` forms (`a & b`, `~(a | b)`, `s ? b : a`, ...).  No buses.
+    fn from_verilog(s: &str) -> Result<Self, NetlistParseError> {
+        let without_comments: Vec<_> = s
+            .lines()
+            .map(|line| line.split("//").next().unwrap_or_default())
+            .collect();
+        let mut netlist = Netlist::default();
+        for statement in without_comments.join("\n").split(';') {
+            let statement = statement.trim();
+            let err = |what: &str| NetlistParseError(format!("{what}: `{statement}`"));
+            let (keyword, rest) = statement
+                .split_once(char::is_whitespace)
+                .unwrap_or((statement, ""));
+            let names = || {
+                rest.split(',')
+                    .map(|w| w.trim().to_owned())
+                    .filter(|w| !w.is_empty())
+            };
+            match keyword {
+                "" | "endmodule" | "wire" => (),
+                "module" => {
+                    let name = rest.split('(').next().unwrap_or_default().trim();
+                    netlist.name = name.to_owned();
+                }
+                "input" => netlist.inputs.extend(names()),
+                "output" => netlist.outputs.extend(names()),
+                "assign" => {
+                    let (out, expr) = rest.split_once('=').ok_or_else(|| err("no `=`"))?;
+                    let expr: String = expr.chars().filter(|c| !c.is_whitespace()).collect();
+                    let gate = parse_verilog_expression(out.trim(), &expr)
+                        .ok_or_else(|| err("unsupported expression"))?;
+                    netlist.gates.push(gate);
+                }
+                _ => {
+                    let op = GateOp::ALL
+                        .into_iter()
+                        .find(|op| op.verilog_primitive() == Some(keyword))
+                        .ok_or_else(|| err("unknown statement"))?;
+                    let ports = rest
+                        .split_once('(')
+                        .and_then(|(_instance, ports)| ports.trim().strip_suffix(')'))
+                        .ok_or_else(|| err("no port list"))?;
+                    let ports: Vec<_> = ports.split(',').map(|p| p.trim()).collect();
+                    if ports.len() != op.arity() + 1 {
+                        return Err(err("wrong number of ports"));
+                    }
+                    netlist
+                        .gates
+                        .push(Gate::from_inputs(op, &ports[1..], ports[0]));
                 }
             }
-            // not ready yet; try again later
-            future.push([i0, op, i1, out]);
         }
-        std::mem::swap(&mut active, &mut future);
+        Ok(netlist)
+    }
+
+    fn to_blif(&self) -> String {
+        let mut b = String::new();
+        let join = |wires: &[String]| wires.join(" ");
+        writeln!(b, ".model {}", self.name).unwrap();
+        writeln!(b, ".inputs {}", join(&self.inputs)).unwrap();
+        writeln!(b, ".outputs {}", join(&self.outputs)).unwrap();
+        for gate in self.gates.iter() {
+            let ins: Vec<_> = gate.inputs().into_iter().map(|w| w.as_str()).collect();
+            writeln!(b, ".names {} {}", ins.join(" "), gate.out).unwrap();
+            for row in gate.op.blif_cover() {
+                writeln!(b, "{row}").unwrap();
+            }
+        }
+        b.push_str(".end\n");
+        b
+    }
+
+    /// Each `.names` table must compute one of the [`GateOp`]s, whichever
+    /// rows it uses to do so.
+    fn from_blif(s: &str) -> Result<Self, NetlistParseError> {
+        let mut lines = vec![];
+        let mut continued = String::new();
+        for line in s.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim_end();
+            if let Some(head) = line.strip_suffix('\\') {
+                continued.push_str(head);
+                continued.push(' ');
+                continue;
+            }
+            continued.push_str(line);
+            if !continued.trim().is_empty() {
+                lines.push(std::mem::take(&mut continued));
+            }
+            continued.clear();
+        }
+        let mut netlist = Netlist::default();
+        // `.names` header, then its rows
+        let mut table: Option<(Vec<&str>, Vec<&str>)> = None;
+        for line in lines.iter().map(|l| l.trim()) {
+            if !line.starts_with('.') {
+                let (_header, rows) = table
+                    .as_mut()
+                    .ok_or_else(|| NetlistParseError(format!("row outside `.names`: {line}")))?;
+                rows.push(line);
+                continue;
+            }
+            if let Some((header, rows)) = table.take() {
+                netlist.gates.push(gate_from_blif_table(&header, &rows)?);
+            }
+            let mut words = line.split_ascii_whitespace();
+            let directive = words.next().unwrap_or_default();
+            match directive {
+                ".model" => netlist.name = words.collect::<Vec<_>>().join(" "),
+                ".inputs" => netlist.inputs.extend(words.map(|w| w.to_owned())),
+                ".outputs" => netlist.outputs.extend(words.map(|w| w.to_owned())),
+                ".names" => table = Some((words.collect(), vec![])),
+                ".end" => break,
+                _ => return Err(NetlistParseError(format!("unsupported: {line}"))),
+            }
+        }
+        if let Some((header, rows)) = table.take() {
+            netlist.gates.push(gate_from_blif_table(&header, &rows)?);
+        }
+        Ok(netlist)
     }
 }
 
-fn into_zulu(state: HashMap<String, bool>) -> u64 {
-    state
-        .iter()
-        .filter(|(id, _val)| &id[0..1] == "z")
-        .fold(0_u64, |acc, (id, &val)| {
-            let index = id[1..].parse::<u32>().unwrap();
-            acc | ((val as u64) << index)
+/// One operator over plain wire names; anything more nested, like `~a & b`
+/// or `a & b | c`, is `None` rather than a gate with an odd wire name.
+fn parse_verilog_expression(out: &str, expr: &str) -> Option<Gate> {
+    let binary = |expr: &str, ops: &[(char, GateOp)]| {
+        ops.iter().find_map(|&(symbol, op)| {
+            let (a, b) = expr.split_once(symbol)?;
+            Some(Gate::new(op, a, b, out))
         })
+    };
+    let gate = if let Some((sel, b_a)) = expr.split_once('?') {
+        let (b, a) = b_a.split_once(':')?;
+        Gate::mux(sel, a, b, out)
+    } else if let Some(inner) = expr.strip_prefix("~(").and_then(|e| e.strip_suffix(')')) {
+        binary(inner, &[('&', GateOp::Nand), ('|', GateOp::Nor)])?
+    } else if let Some(a) = expr.strip_prefix('~') {
+        Gate::new(GateOp::Not, a, a, out)
+    } else {
+        binary(
+            expr,
+            &[('&', GateOp::And), ('|', GateOp::Or), ('^', GateOp::Xor)],
+        )?
+    };
+    let is_plain = gate
+        .inputs()
+        .into_iter()
+        .chain([&gate.out])
+        .all(|w| is_verilog_identifier(w));
+    is_plain.then_some(gate)
 }
 
-const SWAPPED_GATES: usize = 4;
-#[allow(unused)]
-fn do_part_two(mut gates: Vec<[String; 4]>) -> String {
-    let mut swaps = vec![];
-    for _ in 0..SWAPPED_GATES {
-        let [i, j] = biggest_change(&mut gates);
-        swaps.push(gates[i][3].clone());
-        swaps.push(gates[j][3].clone());
-        // keep that swap
-        let (left, right) = gates.split_at_mut(j);
-        std::mem::swap(&mut left[i][3], &mut right[0][3]);
-    }
-    swaps.sort_unstable();
-    let mut retval = String::new();
-    for id in swaps {
-        retval.push_str(&id);
-        retval.push(',');
-    }
-    let _trailing_comma = retval.pop();
-    retval
+/// Simple identifiers only, not escaped ones like `\a+b `.
+fn is_verilog_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
 }
 
-fn biggest_change(gates: &mut [[String; 4]]) -> [usize; 2] {
-    let mut fixed = u32::MAX;
-    let mut best = [0, 0];
-    for i in 0..gates.len() {
-        for j in i + 1..gates.len() {
-            {
-                // swap outputs
-                let (left, right) = gates.split_at_mut(j);
-                std::mem::swap(&mut left[i][3], &mut right[0][3]);
+/// Evaluates the rows on every input combination at once, one lane each, then
+/// looks for the operator with the same truth table.
+fn gate_from_blif_table(header: &[&str], rows: &[&str]) -> Result<Gate, NetlistParseError> {
+    let err = |what: &str| NetlistParseError(format!("{what}: .names {}", header.join(" ")));
+    let (out, inputs) = header.split_last().ok_or_else(|| err("no output"))?;
+    let k = inputs.len();
+    if !(1..=3).contains(&k) {
+        return Err(err("unsupported number of inputs"));
+    }
+    let lanes = 1_u64 << k;
+    let mask = (1_u64 << lanes) - 1;
+    // Lane `l` sets input `j` to bit `j` of `l`.
+    let words: Vec<u64> = (0..k)
+        .map(|j| {
+            (0..lanes)
+                .filter(|l| (l >> j) & 1 == 1)
+                .fold(0, |acc, l| acc | (1 << l))
+        })
+        .collect();
+    let mut matched = 0_u64;
+    let mut polarity = None;
+    for row in rows {
+        let (pattern, value) = row
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| err("row without output"))?;
+        let pattern = pattern.as_bytes();
+        if pattern.len() != k || polarity.is_some_and(|p| p != value.trim()) {
+            return Err(err("malformed row"));
+        }
+        polarity = Some(value.trim());
+        let hits = pattern
+            .iter()
+            .zip(words.iter())
+            .fold(mask, |acc, (c, w)| match c {
+                b'1' => acc & w,
+                b'0' => acc & !w,
+                _ => acc,
+            });
+        matched |= hits;
+    }
+    let table = match polarity {
+        Some("1") | None => matched,
+        Some("0") => !matched & mask,
+        Some(_) => return Err(err("malformed row")),
+    };
+    let op = GateOp::ALL
+        .into_iter()
+        .filter(|op| op.arity() == k)
+        .find(|op| {
+            let got = match op {
+                GateOp::Not => op.apply(words[0], 0, 0),
+                GateOp::Mux => op.apply(words[1], words[2], words[0]),
+                _ => op.apply(words[0], words[1], 0),
+            };
+            got & mask == table
+        })
+        .ok_or_else(|| err("not a known gate"))?;
+    Ok(Gate::from_inputs(op, inputs, out))
+}
+//endregion
+
+#[derive(Debug, PartialEq, Eq)]
+enum NetlistError {
+    UnknownOperator(String),
+    /// Driven by more than one gate, or by a gate and an input.
+    MultipleDrivers(String),
+    /// Read by some gate, but neither an input nor driven by any gate.
+    Floating(String),
+    /// Outputs of every gate that is on, or downstream of, a loop.
+    Cycle(Vec<String>),
+    /// The first wire of an `x`, `y` or `z` bus past the 64 bits that fit in
+    /// a number.
+    BusTooWide(String),
+}
+
+/// Gates sorted once into evaluation order, with wires interned to
+/// `0..names.len()`.  Each wire holds a `u64`, so one pass evaluates 64
+/// independent sets of inputs, one per bit lane.
+#[derive(Debug)]
+struct CompiledNetlist {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    /// `(op, [a, b, sel], out)`, topologically sorted.
+    order: Vec<(GateOp, [usize; 3], usize)>,
+}
+
+impl CompiledNetlist {
+    fn compile<'a>(
+        gates: &[Gate],
+        inputs: impl IntoIterator<Item = &'a String>,
+    ) -> Result<Self, NetlistError> {
+        let mut names = vec![];
+        let mut ids = HashMap::new();
+        let mut intern = |name: &String| -> usize {
+            *ids.entry(name.clone()).or_insert_with(|| {
+                names.push(name.clone());
+                names.len() - 1
+            })
+        };
+        let input_ids: Vec<_> = inputs.into_iter().map(&mut intern).collect();
+        let mut interned = Vec::with_capacity(gates.len());
+        for gate in gates.iter() {
+            let (a, b) = (intern(&gate.a), intern(&gate.b));
+            let sel = gate.sel.as_ref().map_or(a, &mut intern);
+            interned.push((gate.op, [a, b, sel], intern(&gate.out)));
+        }
+        // A wire is either an input, or has exactly one driving gate.
+        let mut is_driven = vec![false; names.len()];
+        for i in input_ids {
+            is_driven[i] = true;
+        }
+        let mut driver = vec![None; names.len()];
+        for (g, &(_op, _ins, out)) in interned.iter().enumerate() {
+            if is_driven[out] {
+                return Err(NetlistError::MultipleDrivers(names[out].clone()));
             }
-            let wrong = how_wrong(&gates);
-            if wrong < fixed {
-                fixed = wrong;
-                best = [i, j];
+            is_driven[out] = true;
+            driver[out] = Some(g);
+        }
+        let mut consumers = vec![vec![]; names.len()];
+        let mut pending_inputs = vec![0_u8; interned.len()];
+        for (g, (gate, &(op, ins, _out))) in gates.iter().zip(interned.iter()).enumerate() {
+            // `Not` reads only `a`, and only `Mux` reads `sel`.
+            let ins = match op {
+                GateOp::Not => &ins[..1],
+                GateOp::Mux if gate.sel.is_some() => &ins[..],
+                _ => &ins[..2],
+            };
+            for &w in ins {
+                if !is_driven[w] {
+                    return Err(NetlistError::Floating(names[w].clone()));
+                }
+                if driver[w].is_some() {
+                    consumers[w].push(g);
+                    pending_inputs[g] += 1;
+                }
+            }
+        }
+        // Kahn's algorithm
+        let mut ready: Vec<usize> = (0..interned.len())
+            .filter(|&g| pending_inputs[g] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(interned.len());
+        while let Some(g) = ready.pop() {
+            let gate = interned[g];
+            order.push(gate);
+            for &c in consumers[gate.2].iter() {
+                pending_inputs[c] -= 1;
+                if pending_inputs[c] == 0 {
+                    ready.push(c);
+                }
             }
-            {
-                // swap back
-                let (left, right) = gates.split_at_mut(j);
-                std::mem::swap(&mut left[i][3], &mut right[0][3]);
+        }
+        if order.len() < interned.len() {
+            let mut stuck: Vec<_> = (0..interned.len())
+                .filter(|&g| pending_inputs[g] > 0)
+                .map(|g| names[interned[g].2].clone())
+                .collect();
+            stuck.sort_unstable();
+            return Err(NetlistError::Cycle(stuck));
+        }
+        for prefix in ['x', 'y', 'z'] {
+            let wire = |pos: u32| format!("{prefix}{pos:02}");
+            if (0..=u64::BITS).all(|pos| ids.contains_key(&wire(pos))) {
+                return Err(NetlistError::BusTooWide(wire(u64::BITS)));
             }
         }
+        Ok(Self { names, ids, order })
     }
-    // println!("Found {best:?}");
-    best
-}
-
-fn how_wrong(gates: &[[String; 4]]) -> u32 {
-    // making waves
-    // Two 45-bit numbers go in, one 46-bit number comes out.
-    let mut dominoes = HashMap::new();
-    for pos in 0..45 {
-        let x_id = format!("x{pos:02}");
-        dominoes.insert(x_id, true);
-        let y_id = format!("y{pos:02}");
-        dominoes.insert(y_id, false);
-    }
-    dominoes.insert("y00".to_owned(), true);
-    simulate(&mut dominoes, gates);
-    let got = into_zulu(dominoes);
-    let want: u64 = 1 << 45;
-    let wrong = (want ^ got).count_ones();
-    wrong
-}
-
-fn generate_graphviz(gates: &[[String; 4]], input_length: u32) -> String {
-    // Rust is probably the wrong tool for this.
-    let mut gv = String::new();
-    gv.push_str("digraph aoc24 {\n");
-    {
-        for x in ['x', 'y', 'z'] {
-            gv.push_str("subgraph external_");
-            gv.push(x);
-            gv.push_str(" { node [shape=circle,style=filled,color=yellow]; \n");
-            let input_length = if x == 'z' {
-                input_length + 1
-            } else {
-                input_length
-            };
-            for pos in 0..input_length {
-                let id = format!("{x}{pos:02} -> ");
-                gv.push_str(id.as_str());
+
+    fn blank_values(&self) -> Vec<u64> {
+        vec![0; self.names.len()]
+    }
+
+    /// Expects inputs to be filled in already; fills in everything else.
+    fn evaluate(&self, values: &mut [u64]) {
+        for &(op, [a, b, sel], out) in self.order.iter() {
+            values[out] = op.apply(values[a], values[b], values[sel]);
+        }
+    }
+
+    /// Wires `{prefix}00`, `{prefix}01`, ... until the first one that is missing.
+    /// At most 64 for `x`, `y` and `z`, which [`Self::compile`] checks.
+    fn bus(&self, prefix: char) -> Vec<usize> {
+        (0..)
+            .map_while(|pos| self.ids.get(&format!("{prefix}{pos:02}")).copied())
+            .collect()
+    }
+
+    /// Inverse of [`Self::read_bus`]: `numbers[lane]` goes into bit lane `lane`.
+    fn write_bus(&self, values: &mut [u64], prefix: char, numbers: &[u64]) {
+        for (pos, w) in self.bus(prefix).into_iter().enumerate() {
+            values[w] = numbers
+                .iter()
+                .enumerate()
+                .fold(0, |acc, (lane, n)| acc | (((n >> pos) & 1) << lane));
+        }
+    }
+
+    /// One number per bit lane.
+    fn read_bus(&self, values: &[u64], prefix: char) -> [u64; 64] {
+        let mut numbers = [0; 64];
+        for (pos, w) in self.bus(prefix).into_iter().enumerate() {
+            for (lane, n) in numbers.iter_mut().enumerate() {
+                *n |= ((values[w] >> lane) & 1) << pos;
             }
-            gv.pop();
-            gv.pop();
-            gv.pop();
-            gv.pop();
-            gv.push_str(";\n}\n");
-        }
-    }
-    let (mut ands, mut ors, mut xors, mut inputs) =
-        (String::new(), String::new(), String::new(), String::new());
-    for [i0, op, i1, out] in gates.iter() {
-        inputs.push_str(format!("{i0} -> {out};\t").as_str());
-        inputs.push_str(format!("{i1} -> {out};\n").as_str());
-        match op.as_str() {
-            "AND" => ands.push_str(format!(" {out};").as_str()),
-            "OR" => ors.push_str(format!(" {out};").as_str()),
-            "XOR" => xors.push_str(format!(" {out};").as_str()),
-            _ => panic!("bad input"),
-        }
-    }
-    gv.push_str("subgraph op_ands { node [style=filled,color=red]; \n");
-    gv.push_str(ands.as_str());
-    gv.push_str("\n}\n");
-    gv.push_str("subgraph op_xors { node [style=filled,color=green]; \n");
-    gv.push_str(xors.as_str());
-    gv.push_str("\n}\n");
-    gv.push_str("subgraph op_ors { node [style=filled,color=blue]; \n");
-    gv.push_str(ors.as_str());
-    gv.push_str("\n}\n");
-    //
-    gv.push_str(inputs.as_str());
-    gv.push_str("}\n");
+        }
+        numbers
+    }
+}
+
+const SWAPPED_GATES: usize = 4;
+/// Names the mis-wired outputs by checking each gate against its role in a
+/// ripple-carry adder, then pairing up the suspects until the sum comes out right.
+fn do_part_two(gates: &[Gate], input_length: u32, suspects: &[Miswiring]) -> String {
+    let swaps = propose_swaps(gates, input_length, suspects).expect("adder should be repairable");
+    assert_eq!(
+        SWAPPED_GATES,
+        swaps.len(),
+        "puzzle promises exactly four swaps"
+    );
+    let mut swapped: Vec<_> = swaps.into_iter().flatten().collect();
+    swapped.sort_unstable();
+    swapped.join(",")
+}
+
+/// A gate whose output is not wired the way its role in the adder requires.
+#[derive(Clone, Debug)]
+struct Miswiring {
+    out: String,
+    op: GateOp,
+    reason: &'static str,
+}
+
+/// Bit `i` of a ripple-carry adder is one full-adder cell:
+///
+/// ```text
+/// t = x XOR y     a = x AND y
+/// z = t XOR c     b = t AND c     c' = a OR b
+/// ```
+///
+/// Bit `0` is a half adder (`z00 = x00 XOR y00`, `c = x00 AND y00`), and the
+/// final carry comes out on `z{input_length}`.  Each rule below only looks at a
+/// gate's own operator, inputs, and consumers, so a gate that no swap touched
+/// never breaks one.  The converse does not hold: swapping two gates with the
+/// same role, such as two carry ANDs or two sum XORs of different bits,
+/// passes every rule, and only [`adds_correctly`] can tell.
+fn find_miswired_outputs(gates: &[Gate], input_length: u32) -> Vec<Miswiring> {
+    use GateOp::{And, Or, Xor};
+    let z_last = format!("z{input_length:02}");
+    let mut consumers: HashMap<&str, Vec<GateOp>> = HashMap::new();
+    for gate in gates.iter() {
+        for wire in gate.inputs() {
+            consumers.entry(wire).or_default().push(gate.op);
+        }
+    }
+    let feeds = |wire: &str, op| consumers.get(wire).is_some_and(|ops| ops.contains(&op));
+    let is_carry = |wire: &str| wire == z_last || (feeds(wire, Xor) && feeds(wire, And));
+    let is_input = |wire: &str| is_bus_wire(wire, 'x') || is_bus_wire(wire, 'y');
+    let mut retval = vec![];
+    for Gate { op, a, b, out, .. } in gates.iter() {
+        let from_inputs = is_input(a) && is_input(b);
+        let is_first_bit = from_inputs && a.ends_with("00") && b.ends_with("00");
+        let is_z = is_bus_wire(out, 'z');
+        let reason = match op {
+            Xor if is_first_bit => (out != "z00").then_some("half-adder sum must be z00"),
+            Xor if from_inputs => (is_z || !(feeds(out, Xor) && feeds(out, And)))
+                .then_some("x XOR y must feed the sum XOR and the carry AND"),
+            Xor => (!is_z || *out == z_last).then_some("sum XOR must drive a z output"),
+            And if is_first_bit => (!is_carry(out)).then_some("half-adder AND is a carry"),
+            And => (is_z || !feeds(out, Or)).then_some("AND must feed the carry OR"),
+            Or => (!is_carry(out)).then_some("carry OR must feed the next bit"),
+            _ => Some("not part of a ripple-carry adder"),
+        };
+        if let Some(reason) = reason {
+            retval.push(Miswiring {
+                out: out.clone(),
+                op: *op,
+                reason,
+            });
+        }
+    }
+    retval
+}
+
+/// Part of bus `prefix`, like `x07`, as opposed to an internal wire that just
+/// happens to start with the same letter, like `xkq`.
+fn is_bus_wire(wire: &str, prefix: char) -> bool {
+    wire.strip_prefix(prefix)
+        .is_some_and(|pos| !pos.is_empty() && pos.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// A gate that is not part of a swap keeps its operator, inputs, and consumers,
+/// so every suspect must be in a swap.  Assuming the swaps are only among
+/// suspects, the smallest repair is a perfect matching of them; tries each
+/// matching until one passes both the structural check and [`adds_correctly`].
+/// `None` when none does, as when two gates of the same role were swapped and
+/// so neither is a suspect.
+fn propose_swaps(
+    gates: &[Gate],
+    input_length: u32,
+    suspects: &[Miswiring],
+) -> Option<Vec<[String; 2]>> {
+    let mut wires: Vec<_> = suspects.iter().map(|m| m.out.clone()).collect();
+    wires.sort_unstable();
+    wires.dedup();
+    if wires.len() % 2 != 0 {
+        return None;
+    }
+    let mut gates = gates.to_vec();
+    let mut pairs = vec![];
+    if try_matchings(&mut gates, input_length, &mut wires, &mut pairs) {
+        Some(pairs)
+    } else {
+        None
+    }
+}
+
+fn try_matchings(
+    gates: &mut [Gate],
+    input_length: u32,
+    unpaired: &mut Vec<String>,
+    pairs: &mut Vec<[String; 2]>,
+) -> bool {
+    let Some(first) = unpaired.pop() else {
+        return find_miswired_outputs(gates, input_length).is_empty()
+            && adds_correctly(gates, input_length);
+    };
+    for i in 0..unpaired.len() {
+        let second = unpaired.remove(i);
+        swap_outputs(gates, &first, &second);
+        pairs.push([first.clone(), second.clone()]);
+        if try_matchings(gates, input_length, unpaired, pairs) {
+            return true;
+        }
+        let _ = pairs.pop();
+        swap_outputs(gates, &first, &second);
+        unpaired.insert(i, second);
+    }
+    unpaired.push(first);
+    false
+}
+
+fn swap_outputs(gates: &mut [Gate], a: &str, b: &str) {
+    for gate in gates.iter_mut() {
+        if gate.out == a {
+            gate.out = b.to_owned();
+        } else if gate.out == b {
+            gate.out = a.to_owned();
+        }
+    }
+}
+
+const RANDOM_ADDITIONS: usize = 100;
+/// Checks the carry chain end to end, then a batch of random sums, 64 at a time.
+/// Netlists that do not compile, say because of a loop, do not add at all.
+fn adds_correctly(gates: &[Gate], input_length: u32) -> bool {
+    let inputs: Vec<_> = (0..input_length)
+        .flat_map(|pos| [format!("x{pos:02}"), format!("y{pos:02}")])
+        .collect();
+    let Ok(netlist) = CompiledNetlist::compile(gates, inputs.iter()) else {
+        return false;
+    };
+    let mask = (1_u64 << input_length) - 1;
+    let mut rng = XorShift64::new(0x2024_1224);
+    let cases: Vec<_> = [(mask, 1), (1, mask), (mask, mask), (0, 0)]
+        .into_iter()
+        .chain((0..RANDOM_ADDITIONS).map(|_| (rng.next_u64() & mask, rng.next_u64() & mask)))
+        .collect();
+    cases.chunks(64).all(|chunk| {
+        let (xs, ys): (Vec<_>, Vec<_>) = chunk.iter().copied().unzip();
+        let mut values = netlist.blank_values();
+        netlist.write_bus(&mut values, 'x', &xs);
+        netlist.write_bus(&mut values, 'y', &ys);
+        netlist.evaluate(&mut values);
+        let zs = netlist.read_bus(&values, 'z');
+        chunk.iter().zip(zs).all(|(&(x, y), z)| x + y == z)
+    })
+}
+
+fn generate_graphviz(gates: &[Gate], input_length: u32) -> Graph {
+    let mut gv = Graph::digraph("aoc24");
+    for x in ['x', 'y', 'z'] {
+        let mut external = Graph::subgraph(format!("external_{x}"));
+        external
+            .node_attr("shape", "circle")
+            .node_attr("style", "filled")
+            .node_attr("color", "yellow");
+        let input_length = if x == 'z' {
+            input_length + 1
+        } else {
+            input_length
+        };
+        for pos in 0..input_length {
+            external.node(format!("{x}{pos:02}"));
+        }
+        // Chained together to keep each bus in order.
+        for pos in 1..input_length {
+            external.edge(format!("{x}{:02}", pos - 1), format!("{x}{pos:02}"));
+        }
+        gv.add_subgraph(external);
+    }
+    let mut by_op = [
+        ("op_ands", "red"),
+        ("op_xors", "green"),
+        ("op_ors", "blue"),
+        ("op_others", "gray"),
+    ]
+    .map(|(name, color)| {
+        let mut sub = Graph::subgraph(name);
+        sub.node_attr("style", "filled").node_attr("color", color);
+        sub
+    });
+    for gate in gates.iter() {
+        let i = match gate.op {
+            GateOp::And => 0,
+            GateOp::Xor => 1,
+            GateOp::Or => 2,
+            _ => 3,
+        };
+        by_op[i].node(&gate.out);
+        for input in gate.inputs() {
+            gv.edge(input, &gate.out);
+        }
+    }
+    for sub in by_op {
+        gv.add_subgraph(sub);
+    }
     gv
 }
-// Visually:
+// Visually (and confirmed by `do_part_two`):
 // bjm,z07
 // skf,z18
 // hsw,z13
 // nvr,wkr
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Same layout as the puzzle input, with made-up names for internal wires.
+    fn ripple_carry_adder(input_length: u32) -> Vec<Gate> {
+        let gate =
+            |i0: &str, op: &str, i1: &str, out: &str| Gate::new(op.parse().unwrap(), i0, i1, out);
+        let mut gates = vec![gate("x00", "XOR", "y00", "z00")];
+        let mut carry = "c00".to_owned();
+        gates.push(gate("x00", "AND", "y00", &carry));
+        for pos in 1..input_length {
+            let (x, y, z) = (
+                format!("x{pos:02}"),
+                format!("y{pos:02}"),
+                format!("z{pos:02}"),
+            );
+            let (t, a, b) = (
+                format!("t{pos:02}"),
+                format!("a{pos:02}"),
+                format!("b{pos:02}"),
+            );
+            let next_carry = if pos + 1 == input_length {
+                format!("z{input_length:02}")
+            } else {
+                format!("c{pos:02}")
+            };
+            gates.push(gate(&x, "XOR", &y, &t));
+            gates.push(gate(&t, "XOR", &carry, &z));
+            gates.push(gate(&y, "AND", &x, &a));
+            gates.push(gate(&carry, "AND", &t, &b));
+            gates.push(gate(&a, "OR", &b, &next_carry));
+            carry = next_carry;
+        }
+        gates
+    }
+
+    fn adder_netlist(input_length: u32) -> Netlist {
+        Netlist {
+            name: "adder".to_owned(),
+            inputs: (0..input_length)
+                .flat_map(|pos| [format!("x{pos:02}"), format!("y{pos:02}")])
+                .collect(),
+            outputs: (0..=input_length).map(|pos| format!("z{pos:02}")).collect(),
+            gates: ripple_carry_adder(input_length),
+        }
+    }
+
+    #[test]
+    fn cycle() {
+        let mut gates = ripple_carry_adder(4);
+        // The sum XOR now drives the carry that it reads.
+        swap_outputs(&mut gates, "z02", "c01");
+        let inputs = ["x00", "y00", "x01", "y01", "x02", "y02", "x03", "y03"].map(String::from);
+        match CompiledNetlist::compile(&gates, inputs.iter()) {
+            Err(NetlistError::Cycle(stuck)) => assert!(stuck.contains(&"c01".to_owned())),
+            other => panic!("expected a cycle, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn floating() {
+        let gates = ripple_carry_adder(4);
+        let inputs = ["x00", "y00", "x01", "y01", "x02", "x03", "y03"].map(String::from);
+        assert_eq!(
+            NetlistError::Floating("y02".to_owned()),
+            CompiledNetlist::compile(&gates, inputs.iter()).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn too_wide() {
+        let inputs = |n: u32| -> Vec<String> {
+            (0..n)
+                .flat_map(|i| [format!("x{i:02}"), format!("y{i:02}")])
+                .collect()
+        };
+        // the carry out of the top bit is one more output bit
+        let gates = ripple_carry_adder(63);
+        assert!(CompiledNetlist::compile(&gates, inputs(63).iter()).is_ok());
+        let gates = ripple_carry_adder(64);
+        assert_eq!(
+            NetlistError::BusTooWide("z64".to_owned()),
+            CompiledNetlist::compile(&gates, inputs(64).iter()).unwrap_err()
+        );
+    }
+
+    fn every_op() -> Netlist {
+        let mut gates: Vec<_> = [
+            GateOp::And,
+            GateOp::Or,
+            GateOp::Xor,
+            GateOp::Nand,
+            GateOp::Nor,
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, op)| Gate::new(op, "a", "b", &format!("w{i}")))
+        .collect();
+        gates.push(Gate::new(GateOp::Not, "w0", "w0", "w5"));
+        gates.push(Gate::mux("s", "w5", "w4", "out"));
+        Netlist {
+            name: "every_op".to_owned(),
+            inputs: vec!["a".to_owned(), "b".to_owned(), "s".to_owned()],
+            outputs: vec!["out".to_owned()],
+            gates,
+        }
+    }
+
+    #[test]
+    fn round_trip_verilog() {
+        for netlist in [every_op(), adder_netlist(6)] {
+            let v = netlist.to_verilog();
+            assert_eq!(Ok(netlist), Netlist::from_verilog(&v));
+        }
+    }
+
+    #[test]
+    fn round_trip_blif() {
+        for netlist in [every_op(), adder_netlist(6)] {
+            let b = netlist.to_blif();
+            assert_eq!(Ok(netlist), Netlist::from_blif(&b));
+        }
+    }
+
+    #[test]
+    fn foreign_syntax() {
+        let v = "module m (a, b, s, y); // comment\n input a, b, s;\n output y;\n wire n;\n\
+                 assign n = ~(a | b);\n assign y = s ? n : a;\nendmodule\n";
+        let netlist = Netlist::from_verilog(v).unwrap();
+        assert_eq!(
+            vec![
+                Gate::new(GateOp::Nor, "a", "b", "n"),
+                Gate::mux("s", "a", "n", "y")
+            ],
+            netlist.gates
+        );
+        for expr in ["~a & b", "a & b | c", "a & ~b", "s ? a : b ? a : n", "(a)"] {
+            let v = v.replace("~(a | b)", expr);
+            assert!(Netlist::from_verilog(&v).is_err(), "{expr}");
+        }
+        // XNOR, as the off-set of an XOR; then a NAND spelled out in full.
+        let b = ".model m\n.inputs a b\n.outputs y\n.names a b n\n10 0\n01 0\n\
+                 .names a \\\n b y # continued\n00 1\n01 1\n10 1\n.end\n";
+        assert!(Netlist::from_blif(b).is_err());
+        let b = b.replace("10 0\n01 0", "10 1\n01 1");
+        let netlist = Netlist::from_blif(&b).unwrap();
+        assert_eq!(
+            vec![
+                Gate::new(GateOp::Xor, "a", "b", "n"),
+                Gate::new(GateOp::Nand, "a", "b", "y")
+            ],
+            netlist.gates
+        );
+    }
+
+    #[test]
+    fn intact_adder() {
+        let gates = ripple_carry_adder(8);
+        assert!(find_miswired_outputs(&gates, 8).is_empty());
+        assert!(adds_correctly(&gates, 8));
+        // internal wires named like the buses, as any imported netlist may
+        let mut gates = gates;
+        let rename = |wire: &mut String| match wire.chars().next() {
+            Some('t') => wire.insert(0, 'x'),
+            Some('a') => wire.insert(0, 'y'),
+            Some('b') => wire.insert(0, 'z'),
+            _ => (),
+        };
+        for gate in gates.iter_mut() {
+            [&mut gate.a, &mut gate.b, &mut gate.out]
+                .into_iter()
+                .for_each(rename);
+        }
+        assert!(find_miswired_outputs(&gates, 8).is_empty());
+        assert!(adds_correctly(&gates, 8));
+    }
+
+    #[test]
+    fn repairs_swaps() {
+        let mut gates = ripple_carry_adder(12);
+        swap_outputs(&mut gates, "z03", "c03");
+        swap_outputs(&mut gates, "t07", "a07");
+        swap_outputs(&mut gates, "z10", "b10");
+        let suspects = find_miswired_outputs(&gates, 12);
+        assert!(!adds_correctly(&gates, 12));
+        let mut swaps = propose_swaps(&gates, 12, &suspects).expect("repairable");
+        swaps.iter_mut().for_each(|pair| pair.sort_unstable());
+        swaps.sort_unstable();
+        assert_eq!(vec![["a07", "t07"], ["b10", "z10"], ["c03", "z03"]], swaps);
+    }
+}
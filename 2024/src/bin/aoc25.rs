@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     fs::File,
     io::{BufRead, BufReader},
 };
@@ -9,69 +10,346 @@ fn main() -> std::io::Result<()> {
     let path_input = args.get(1).expect("Should have an input file");
     // Part one
     let f = File::open(path_input)?;
-    let (keys, locks) = parse_input(BufReader::new(f));
+    let schematics = parse_input(BufReader::new(f)).expect("sane input");
     // Have 250 of each.
-    // println!("{keys:?}");
-    // println!("{locks:?}");
+    // println!("{schematics:?}");
     // Part one
-    let p1 = do_part_one(&keys, &locks, 5);
+    let p1 = if args.iter().any(|a| a == "--brute-force") {
+        fitting_pairs(&schematics.keys, &schematics.locks).count() as u64
+    } else {
+        do_part_one(&schematics)
+    };
     dbg!(p1);
+    if args.iter().any(|a| a == "--pairs") {
+        for (lock, key) in fitting_pairs(&schematics.keys, &schematics.locks) {
+            let (l, k) = (&schematics.locks[lock], &schematics.keys[key]);
+            println!("lock {lock} {:?} fits key {key} {:?}", l.heights, k.heights);
+        }
+    }
     // Part two
     // let p2 = do_part_two();
     // dbg!(p2);
     Ok(())
 }
 
-fn parse_input(r: BufReader<File>) -> (Vec<Profile>, Vec<Profile>) {
-    let mut keys = vec![];
-    let mut locks = vec![];
-    let mut profile = Profile::default();
-    let mut is_lock = None;
+fn parse_input(r: impl BufRead) -> Result<Schematics, SchematicError> {
+    let mut schematics = Schematics::default();
+    let mut rows: Vec<String> = vec![];
     for line in r
         .lines()
         .flat_map(|l| l.ok().into_iter())
         .chain(["".into()])
     {
-        if line.is_empty() {
-            profile.0.iter_mut().for_each(|h| *h -= 1);
-            if is_lock.unwrap() {
-                locks.push(profile);
+        if !line.is_empty() {
+            rows.push(line);
+            continue;
+        }
+        if rows.is_empty() {
+            continue;
+        }
+        let index = schematics.keys.len() + schematics.locks.len();
+        let (is_lock, profile) = Profile::new(&rows).map_err(|e| (index, e))?;
+        let size = (rows[0].len(), rows.len());
+        if index == 0 {
+            schematics.size = size;
+        } else if size != schematics.size {
+            return Err((index, SchematicProblem::SizeDiffers));
+        }
+        if is_lock {
+            schematics.locks.push(profile);
+        } else {
+            schematics.keys.push(profile);
+        }
+        rows.clear();
+    }
+    Ok(schematics)
+}
+
+#[derive(Clone, Debug, Default)]
+struct Schematics {
+    /// (columns, rows), the same for every schematic
+    size: (usize, usize),
+    keys: Vec<Profile>,
+    locks: Vec<Profile>,
+}
+
+/// Which schematic (counting from zero in file order) and what is wrong
+/// with it.
+type SchematicError = (usize, SchematicProblem);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SchematicProblem {
+    /// Rows of different lengths, or something other than `#` and `.`.
+    Ragged,
+    /// Not the same size as the first schematic.
+    SizeDiffers,
+    /// Neither the top row nor the bottom row is solid, with the other
+    /// empty.
+    NeitherLockNorKey,
+    /// A column has a gap in it, so it is not a pin or a key cut.
+    Gap { column: usize },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Profile {
+    /// Per column, not counting the solid row.
+    heights: Vec<u32>,
+    /// Every `#`, row by row.  A lock and a key fit when these do not
+    /// overlap.
+    mask: Vec<u64>,
+}
+
+impl Profile {
+    /// Whether it is a lock, and its profile.
+    fn new(rows: &[String]) -> Result<(bool, Profile), SchematicProblem> {
+        let width = rows[0].len();
+        let is_valid = |row: &String| row.len() == width && row.bytes().all(|b| b"#.".contains(&b));
+        if !rows.iter().all(is_valid) {
+            return Err(SchematicProblem::Ragged);
+        }
+        let is_solid = |row: &String| row.bytes().all(|b| b == b'#');
+        let is_empty = |row: &String| row.bytes().all(|b| b == b'.');
+        let (top, bottom) = (&rows[0], &rows[rows.len() - 1]);
+        let is_lock = if is_solid(top) && is_empty(bottom) {
+            true
+        } else if is_empty(top) && is_solid(bottom) {
+            false
+        } else {
+            return Err(SchematicProblem::NeitherLockNorKey);
+        };
+        let mut heights = vec![0; width];
+        let mut mask = vec![0_u64; (width * rows.len()).div_ceil(64)];
+        for (column, height) in heights.iter_mut().enumerate() {
+            let cells = rows.iter().map(|row| row.as_bytes()[column] == b'#');
+            let filled: Vec<bool> = if is_lock {
+                cells.collect()
             } else {
-                keys.push(profile);
+                cells.rev().collect()
+            };
+            let count = filled.iter().take_while(|&&f| f).count();
+            if filled[count..].iter().any(|&f| f) {
+                return Err(SchematicProblem::Gap { column });
             }
-            profile = Profile::default();
-            is_lock = None;
-            continue;
+            *height = count as u32 - 1;
         }
-        if is_lock.is_none() {
-            is_lock = Some(line.chars().next().unwrap() == '#');
+        for (i, b) in rows.iter().flat_map(|row| row.bytes()).enumerate() {
+            if b == b'#' {
+                mask[i / 64] |= 1 << (i % 64);
+            }
         }
-        for (i, ch) in line.char_indices() {
-            let mass = (ch == '#') as i32;
-            profile.0[i] += mass;
+        Ok((is_lock, Profile { heights, mask }))
+    }
+
+    fn fits(&self, other: &Profile) -> bool {
+        self.mask
+            .iter()
+            .zip(other.mask.iter())
+            .all(|(a, b)| a & b == 0)
+    }
+}
+
+/// How many lock/key pairs could fit, without trying every pair: keys go
+/// in a trie by column height, and each distinct lock only visits the
+/// branches short enough for it.
+fn do_part_one(schematics: &Schematics) -> u64 {
+    let (width, rows) = schematics.size;
+    // a lock pin and a key cut in the same column may not meet
+    let space = rows.saturating_sub(2) as u32;
+    let mut trie = HeightTrie::default();
+    for key in schematics.keys.iter() {
+        trie.insert(&key.heights);
+    }
+    let mut locks: HashMap<&[u32], u64> = HashMap::new();
+    for lock in schematics.locks.iter() {
+        *locks.entry(&lock.heights).or_default() += 1;
+    }
+    let mut could_fit = 0;
+    let mut limits = vec![0; width];
+    for (heights, &count) in locks.iter() {
+        for (limit, &h) in limits.iter_mut().zip(heights.iter()) {
+            *limit = space - h;
         }
+        could_fit += count * trie.count_within(&limits);
     }
-    (keys, locks)
+    could_fit
+}
+
+/// Column heights, one level per column; leaves count how many keys share
+/// all of them.
+#[derive(Clone, Debug, Default)]
+struct HeightTrie {
+    /// Node 0 is the root.  Children sorted by height.
+    children: Vec<Vec<(u32, usize)>>,
+    counts: Vec<u64>,
 }
 
-#[derive(Clone, Copy, Debug, Default)]
-struct Profile([i32; 5]);
+impl HeightTrie {
+    fn insert(&mut self, heights: &[u32]) {
+        if self.children.is_empty() {
+            self.children.push(vec![]);
+            self.counts.push(0);
+        }
+        let mut node = 0;
+        for &h in heights {
+            let children = &self.children[node];
+            node = match children.binary_search_by_key(&h, |&(hh, _)| hh) {
+                Ok(i) => children[i].1,
+                Err(i) => {
+                    let child = self.children.len();
+                    self.children[node].insert(i, (h, child));
+                    self.children.push(vec![]);
+                    self.counts.push(0);
+                    child
+                }
+            };
+        }
+        self.counts[node] += 1;
+    }
+
+    /// Keys no taller than `limits` in every column.
+    fn count_within(&self, limits: &[u32]) -> u64 {
+        if self.children.is_empty() {
+            return 0;
+        }
+        let mut total = 0;
+        let mut todo = vec![(0, 0)];
+        while let Some((node, depth)) = todo.pop() {
+            if depth == limits.len() {
+                total += self.counts[node];
+                continue;
+            }
+            for &(h, child) in self.children[node].iter() {
+                if h > limits[depth] {
+                    break;
+                }
+                todo.push((child, depth + 1));
+            }
+        }
+        total
+    }
+}
 
-fn do_part_one(keys: &[Profile], locks: &[Profile], max_height: i32) -> u64 {
+/// `(lock, key)` for every pair that could fit.
+fn fitting_pairs<'a>(
+    keys: &'a [Profile],
+    locks: &'a [Profile],
+) -> impl Iterator<Item = (usize, usize)> + 'a {
     // `250 * 249 / 2` is small, so brute force works.
-    let mut could_fit = 0;
-    for key in keys {
-        for lock in locks {
-            let overlaps = key
-                .0
-                .iter()
-                .zip(lock.0.iter())
-                .map(|(k, l)| k + l)
-                .any(|h| h > max_height);
-            if !overlaps {
-                could_fit += 1;
+    locks.iter().enumerate().flat_map(move |(l, lock)| {
+        keys.iter()
+            .enumerate()
+            .filter(move |(_, key)| lock.fits(key))
+            .map(move |(k, _)| (l, k))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc2024::rng::XorShift64;
+
+    const EXAMPLE: &str = "\
+#####
+.####
+.####
+.####
+.#.#.
+.#...
+.....
+
+#####
+##.##
+.#.##
+...##
+...#.
+...#.
+.....
+
+.....
+#....
+#....
+#...#
+#.#.#
+#.###
+#####
+
+.....
+.....
+#.#..
+###..
+###.#
+###.#
+#####
+
+.....
+.....
+.....
+#....
+#.#..
+#.#.#
+#####
+";
+
+    #[test]
+    fn example() {
+        let schematics = parse_input(EXAMPLE.as_bytes()).unwrap();
+        assert_eq!((5, 7), schematics.size);
+        assert_eq!(vec![0, 5, 3, 4, 3], schematics.locks[0].heights);
+        assert_eq!(vec![5, 0, 2, 1, 3], schematics.keys[0].heights);
+        assert_eq!(
+            vec![(0, 2), (1, 1), (1, 2)],
+            fitting_pairs(&schematics.keys, &schematics.locks).collect::<Vec<_>>()
+        );
+        assert_eq!(3, do_part_one(&schematics));
+    }
+
+    #[test]
+    fn other_sizes() {
+        let input = "###\n#.#\n...\n...\n\n...\n..#\n.##\n###\n\n...\n...\n.#.\n###\n";
+        let schematics = parse_input(input.as_bytes()).unwrap();
+        assert_eq!((3, 4), schematics.size);
+        assert_eq!(vec![1, 0, 1], schematics.locks[0].heights);
+        assert_eq!(
+            vec![(0, 1)],
+            fitting_pairs(&schematics.keys, &schematics.locks).collect::<Vec<_>>()
+        );
+        let wide = format!("{}\n{}\n", "#".repeat(70), ".".repeat(70));
+        assert_eq!(3, parse_input(wide.as_bytes()).unwrap().locks[0].mask.len());
+        let bad = [
+            ("###\n...\n\n##\n..\n", (1, SchematicProblem::SizeDiffers)),
+            ("###\n.#.\n", (0, SchematicProblem::NeitherLockNorKey)),
+            ("###\n..\n...\n", (0, SchematicProblem::Ragged)),
+            (
+                "###\n#..\n.#.\n...\n",
+                (0, SchematicProblem::Gap { column: 1 }),
+            ),
+        ];
+        for (input, want) in bad {
+            assert_eq!(Some(want), parse_input(input.as_bytes()).err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn indexed_matches_brute_force() {
+        let mut rng = XorShift64::new(0x25);
+        for _ in 0..50 {
+            let (width, rows) = (1 + rng.below(8), 2 + rng.below(7));
+            let mut input = String::new();
+            for i in 0..rng.below(60) {
+                let heights: Vec<usize> = (0..width).map(|_| rng.below(rows - 1)).collect();
+                for y in 0..rows {
+                    for &h in heights.iter() {
+                        let is_lock = i % 3 != 0;
+                        let filled = if is_lock { y <= h } else { rows - 1 - y <= h };
+                        input.push(if filled { '#' } else { '.' });
+                    }
+                    input.push('\n');
+                }
+                input.push('\n');
             }
+            let schematics = parse_input(input.as_bytes()).unwrap();
+            let want = fitting_pairs(&schematics.keys, &schematics.locks).count() as u64;
+            assert_eq!(want, do_part_one(&schematics), "{input}");
         }
     }
-    could_fit
 }
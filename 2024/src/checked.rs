@@ -0,0 +1,74 @@
+//! Integer arithmetic that reports overflow instead of wrapping (which is
+//! what release builds do), so a solver can work in 64 bits and retry in
+//! 128 when some value no longer fits.
+//!
+//! ```text
+//! fn solve<T: Checked + From<u64>>(xs: &[u64]) -> Result<T, Overflow> { .. }
+//! let answer = solve::<u64>(&xs).map(u128::from).or_else(|_| solve::<u128>(&xs))?;
+//! ```
+use std::fmt::{Display, Formatter};
+
+/// Some value did not fit in the integer type being used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Overflow;
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("arithmetic overflow")
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+pub trait Checked: Copy + Sized {
+    fn try_add(self, rhs: Self) -> Result<Self, Overflow>;
+    fn try_sub(self, rhs: Self) -> Result<Self, Overflow>;
+    fn try_mul(self, rhs: Self) -> Result<Self, Overflow>;
+    fn try_pow(self, exp: u32) -> Result<Self, Overflow>;
+}
+
+macro_rules! impl_checked {
+    ($($t:ty),*) => {$(
+        impl Checked for $t {
+            fn try_add(self, rhs: Self) -> Result<Self, Overflow> {
+                self.checked_add(rhs).ok_or(Overflow)
+            }
+            fn try_sub(self, rhs: Self) -> Result<Self, Overflow> {
+                self.checked_sub(rhs).ok_or(Overflow)
+            }
+            fn try_mul(self, rhs: Self) -> Result<Self, Overflow> {
+                self.checked_mul(rhs).ok_or(Overflow)
+            }
+            fn try_pow(self, exp: u32) -> Result<Self, Overflow> {
+                self.checked_pow(exp).ok_or(Overflow)
+            }
+        }
+    )*};
+}
+
+impl_checked!(u64, u128, i64, i128);
+
+/// `Ok` if `x` fits in the narrower type `T`.
+pub fn narrow<T: TryFrom<U>, U>(x: U) -> Result<T, Overflow> {
+    T::try_from(x).map_err(|_| Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflow() {
+        assert_eq!(Ok(u64::MAX), (u64::MAX - 1).try_add(1));
+        assert_eq!(Err(Overflow), u64::MAX.try_add(1));
+        assert_eq!(Err(Overflow), 0_u64.try_sub(1));
+        assert_eq!(Ok(-1), 0_i64.try_sub(1));
+        assert_eq!(Err(Overflow), (1_u64 << 32).try_mul(1 << 32));
+        assert_eq!(Ok(1 << 64), (1_u128 << 32).try_mul(1 << 32));
+        assert_eq!(Ok(10_u64.pow(19)), 10_u64.try_pow(19));
+        assert_eq!(Err(Overflow), 10_u64.try_pow(20));
+        assert_eq!(Ok(10_u128.pow(20)), 10_u128.try_pow(20));
+        assert_eq!(Err(Overflow), narrow::<i64, _>(i64::MAX as i128 + 1));
+        assert_eq!(Ok(i64::MIN), narrow::<i64, _>(i64::MIN as i128));
+    }
+}
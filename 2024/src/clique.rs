@@ -0,0 +1,453 @@
+//! Cliques in undirected graphs given as adjacency lists, where `adj[v]` is
+//! sorted and `w` is in `adj[v]` exactly when `v` is in `adj[w]`.
+//!
+//! Vertices without any edges are ignored, since dense ids (like day 23's
+//! `0..26^2`) leave plenty of them unused.
+
+/// Bron–Kerbosch with pivoting, started from each vertex in degeneracy order
+/// so that no branch has more than `degeneracy` candidates at the top.
+/// Each maximal clique is reported once, sorted.
+pub fn for_each_maximal_clique(adj: &[Vec<usize>], mut f: impl FnMut(&[usize])) {
+    let mut sink = EveryMaximal(|clique: &[usize]| {
+        let mut sorted = clique.to_vec();
+        sorted.sort_unstable();
+        f(&sorted)
+    });
+    search(adj, &mut sink);
+}
+
+pub fn maximal_cliques(adj: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut retval = vec![];
+    for_each_maximal_clique(adj, |clique| retval.push(clique.to_vec()));
+    retval
+}
+
+/// Same search as [`for_each_maximal_clique`], but skips any branch that
+/// cannot beat the largest clique so far.  Ties go to whichever comes first
+/// in degeneracy order.  Sorted.
+pub fn maximum_clique(adj: &[Vec<usize>]) -> Vec<usize> {
+    let mut sink = Largest(vec![]);
+    search(adj, &mut sink);
+    let mut best = sink.0;
+    best.sort_unstable();
+    best
+}
+
+/// Vertices in the order of repeatedly removing one of least remaining
+/// degree, along with the largest such degree.
+pub fn degeneracy_order(adj: &[Vec<usize>]) -> (Vec<usize>, usize) {
+    let n = adj.len();
+    let mut degree: Vec<usize> = adj.iter().map(|to| to.len()).collect();
+    let max_degree = degree.iter().copied().max().unwrap_or(0);
+    // Entries go stale when a degree drops; those are skipped on the way out.
+    let mut buckets = vec![vec![]; max_degree + 1];
+    for (v, &d) in degree.iter().enumerate() {
+        buckets[d].push(v);
+    }
+    let mut removed = vec![false; n];
+    let mut order = Vec::with_capacity(n);
+    let mut degeneracy = 0;
+    let mut d = 0;
+    while order.len() < n {
+        let Some(v) = buckets[d].pop() else {
+            d += 1;
+            continue;
+        };
+        if removed[v] || degree[v] != d {
+            continue;
+        }
+        removed[v] = true;
+        order.push(v);
+        degeneracy = degeneracy.max(d);
+        for &w in adj[v].iter() {
+            if !removed[w] {
+                degree[w] -= 1;
+                buckets[degree[w]].push(w);
+            }
+        }
+        // Removing `v` can only lower a neighbor's degree by one.
+        d = d.saturating_sub(1);
+    }
+    (order, degeneracy)
+}
+
+/// Every `k`-clique with at least one vertex that satisfies `pred`, sorted.
+/// There are none for `k == 0`.  Edges point forward in degeneracy order, so
+/// each clique is built exactly once, by intersecting forward neighbors of
+/// the vertices picked so far.
+pub fn for_each_k_clique(
+    adj: &[Vec<usize>],
+    k: usize,
+    pred: impl Fn(usize) -> bool,
+    mut f: impl FnMut(&[usize]),
+) {
+    if k == 0 {
+        return;
+    }
+    let forward = forward_adjacency(adj);
+    let mut picked = Vec::with_capacity(k);
+    for v in (0..adj.len()).filter(|&v| !adj[v].is_empty()) {
+        picked.push(v);
+        extend_k_clique(
+            &forward,
+            k,
+            &pred,
+            &mut picked,
+            &forward[v],
+            &mut |clique| {
+                let mut sorted = clique.to_vec();
+                sorted.sort_unstable();
+                f(&sorted)
+            },
+        );
+        picked.pop();
+    }
+}
+
+pub fn k_cliques(adj: &[Vec<usize>], k: usize, pred: impl Fn(usize) -> bool) -> Vec<Vec<usize>> {
+    let mut retval = vec![];
+    for_each_k_clique(adj, k, pred, |clique| retval.push(clique.to_vec()));
+    retval
+}
+
+/// Same as counting [`for_each_k_clique`], but the last vertex is only
+/// counted, never picked.
+pub fn count_k_cliques(adj: &[Vec<usize>], k: usize, pred: impl Fn(usize) -> bool) -> u64 {
+    if k == 0 {
+        return 0;
+    }
+    let forward = forward_adjacency(adj);
+    (0..adj.len())
+        .filter(|&v| !adj[v].is_empty())
+        .map(|v| count_extensions(&forward, k - 1, &pred, pred(v), &forward[v]))
+        .sum()
+}
+
+/// `forward[v]` holds the neighbors of `v` that come after it in degeneracy
+/// order, sorted by id.
+fn forward_adjacency(adj: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let (order, _degeneracy) = degeneracy_order(adj);
+    let mut position = vec![0; adj.len()];
+    for (i, &v) in order.iter().enumerate() {
+        position[v] = i;
+    }
+    adj.iter()
+        .enumerate()
+        .map(|(v, to)| {
+            to.iter()
+                .copied()
+                .filter(|&w| position[w] > position[v])
+                .collect()
+        })
+        .collect()
+}
+
+/// `candidates` are adjacent to everything in `picked`, and come after all
+/// of it.
+fn extend_k_clique(
+    forward: &[Vec<usize>],
+    k: usize,
+    pred: &impl Fn(usize) -> bool,
+    picked: &mut Vec<usize>,
+    candidates: &[usize],
+    f: &mut impl FnMut(&[usize]),
+) {
+    if picked.len() == k {
+        if picked.iter().any(|&v| pred(v)) {
+            f(picked);
+        }
+        return;
+    }
+    if candidates.len() < k - picked.len() {
+        return;
+    }
+    if !picked.iter().chain(candidates.iter()).any(|&v| pred(v)) {
+        return;
+    }
+    for &w in candidates.iter() {
+        let next = intersect(candidates, &forward[w]);
+        picked.push(w);
+        extend_k_clique(forward, k, pred, picked, &next, f);
+        picked.pop();
+    }
+}
+
+fn count_extensions(
+    forward: &[Vec<usize>],
+    remaining: usize,
+    pred: &impl Fn(usize) -> bool,
+    has_match: bool,
+    candidates: &[usize],
+) -> u64 {
+    match remaining {
+        0 => has_match as u64,
+        1 if has_match => candidates.len() as u64,
+        1 => candidates.iter().filter(|&&w| pred(w)).count() as u64,
+        _ if candidates.len() < remaining => 0,
+        _ => candidates
+            .iter()
+            .map(|&w| {
+                let next = intersect(candidates, &forward[w]);
+                count_extensions(forward, remaining - 1, pred, has_match || pred(w), &next)
+            })
+            .sum(),
+    }
+}
+
+trait CliqueSink {
+    fn found(&mut self, clique: &[usize]);
+
+    /// Whether a branch that can grow to at most `upper_bound` is worth it.
+    fn worth_exploring(&self, _upper_bound: usize) -> bool {
+        true
+    }
+}
+
+struct EveryMaximal<F>(F);
+
+impl<F: FnMut(&[usize])> CliqueSink for EveryMaximal<F> {
+    fn found(&mut self, clique: &[usize]) {
+        (self.0)(clique)
+    }
+}
+
+struct Largest(Vec<usize>);
+
+impl CliqueSink for Largest {
+    fn found(&mut self, clique: &[usize]) {
+        if clique.len() > self.0.len() {
+            self.0 = clique.to_vec();
+        }
+    }
+
+    fn worth_exploring(&self, upper_bound: usize) -> bool {
+        upper_bound > self.0.len()
+    }
+}
+
+fn search(adj: &[Vec<usize>], sink: &mut impl CliqueSink) {
+    let (order, _degeneracy) = degeneracy_order(adj);
+    let mut position = vec![0; adj.len()];
+    for (i, &v) in order.iter().enumerate() {
+        position[v] = i;
+    }
+    let mut r = vec![];
+    for &v in order.iter() {
+        if adj[v].is_empty() {
+            continue;
+        }
+        // Cliques with an earlier vertex in them have already been found.
+        let (p, x): (Vec<usize>, Vec<usize>) =
+            adj[v].iter().partition(|&&w| position[w] > position[v]);
+        r.push(v);
+        expand(adj, &mut r, p, x, sink);
+        r.pop();
+    }
+}
+
+/// `r` is the clique so far, `p` the vertices that could extend it, and `x`
+/// those that could too but have been tried already.  Both stay sorted.
+fn expand(
+    adj: &[Vec<usize>],
+    r: &mut Vec<usize>,
+    mut p: Vec<usize>,
+    mut x: Vec<usize>,
+    sink: &mut impl CliqueSink,
+) {
+    if p.is_empty() {
+        if x.is_empty() {
+            sink.found(r);
+        }
+        return;
+    }
+    if !sink.worth_exploring(r.len() + p.len()) {
+        return;
+    }
+    // Any maximal clique contains the pivot or one of its non-neighbors, so
+    // only those need a branch each.
+    let pivot = p
+        .iter()
+        .chain(x.iter())
+        .copied()
+        .max_by_key(|&u| count_common(&p, &adj[u]))
+        .expect("p is not empty");
+    let branches: Vec<usize> = p
+        .iter()
+        .copied()
+        .filter(|v| adj[pivot].binary_search(v).is_err())
+        .collect();
+    for v in branches {
+        let pp = intersect(&p, &adj[v]);
+        let xx = intersect(&x, &adj[v]);
+        r.push(v);
+        expand(adj, r, pp, xx, sink);
+        r.pop();
+        if let Ok(i) = p.binary_search(&v) {
+            p.remove(i);
+        }
+        if let Err(i) = x.binary_search(&v) {
+            x.insert(i, v);
+        }
+    }
+}
+
+/// Both sorted.
+pub(crate) fn intersect(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let mut retval = Vec::with_capacity(a.len().min(b.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                retval.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    retval
+}
+
+fn count_common(a: &[usize], b: &[usize]) -> usize {
+    let (mut i, mut j, mut n) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                n += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    n
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift64;
+
+    /// Deterministic G(n, p) with `p = percent / 100`.
+    fn random_graph(n: usize, percent: u64, seed: u64) -> Vec<Vec<usize>> {
+        let mut rng = XorShift64::new(seed);
+        let mut adj = vec![vec![]; n];
+        for a in 0..n {
+            for b in a + 1..n {
+                if rng.next_u64() % 100 < percent {
+                    adj[a].push(b);
+                    adj[b].push(a);
+                }
+            }
+        }
+        adj
+    }
+
+    fn is_clique(adj: &[Vec<usize>], vertices: &[usize]) -> bool {
+        vertices.iter().enumerate().all(|(i, &a)| {
+            vertices[i + 1..]
+                .iter()
+                .all(|b| adj[a].binary_search(b).is_ok())
+        })
+    }
+
+    /// Every subset, as a bitmask; only for small graphs.
+    fn brute_force_maximal(adj: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let n = adj.len();
+        let members =
+            |mask: u32| -> Vec<usize> { (0..n).filter(|i| mask & (1 << i) != 0).collect() };
+        let mut retval = vec![];
+        for mask in 1_u32..(1 << n) {
+            let vertices = members(mask);
+            if vertices.len() == 1 && adj[vertices[0]].is_empty() {
+                continue;
+            }
+            if !is_clique(adj, &vertices) {
+                continue;
+            }
+            let is_maximal = (0..n)
+                .filter(|i| mask & (1 << i) == 0)
+                .all(|i| !is_clique(adj, &members(mask | (1 << i))));
+            if is_maximal {
+                retval.push(vertices);
+            }
+        }
+        retval
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        for seed in 1..=20 {
+            let adj = random_graph(12, 20 + 3 * seed, seed);
+            let mut want = brute_force_maximal(&adj);
+            want.sort_unstable();
+            let mut got = maximal_cliques(&adj);
+            got.sort_unstable();
+            assert_eq!(want, got, "seed {seed}");
+            let largest = want.iter().map(|c| c.len()).max().unwrap_or(0);
+            let best = maximum_clique(&adj);
+            assert_eq!(largest, best.len(), "seed {seed}");
+            assert!(is_clique(&adj, &best));
+        }
+    }
+
+    #[test]
+    fn k_cliques_match_brute_force() {
+        for seed in 1..=10 {
+            let adj = random_graph(14, 30 + 4 * seed, seed);
+            let pred = |v: usize| v.is_multiple_of(5);
+            for k in 1..=5 {
+                let mut want = vec![];
+                for mask in 1_u32..(1 << adj.len()) {
+                    let vertices: Vec<_> =
+                        (0..adj.len()).filter(|i| mask & (1 << i) != 0).collect();
+                    if vertices.len() == k
+                        && !(k == 1 && adj[vertices[0]].is_empty())
+                        && is_clique(&adj, &vertices)
+                        && vertices.iter().any(|&v| pred(v))
+                    {
+                        want.push(vertices);
+                    }
+                }
+                want.sort_unstable();
+                let mut got = k_cliques(&adj, k, pred);
+                got.sort_unstable();
+                assert_eq!(want, got, "seed {seed}, k {k}");
+                assert_eq!(want.len() as u64, count_k_cliques(&adj, k, pred));
+            }
+        }
+    }
+
+    #[test]
+    fn tiny_k() {
+        // 0 - 1, and 2 with no edges at all
+        let adj = vec![vec![1], vec![0], vec![]];
+        let any = |_| true;
+        assert!(k_cliques(&adj, 0, any).is_empty());
+        assert_eq!(0, count_k_cliques(&adj, 0, any));
+        assert_eq!(vec![vec![0], vec![1]], k_cliques(&adj, 1, any));
+        assert_eq!(2, count_k_cliques(&adj, 1, any));
+        assert_eq!(vec![vec![0, 1]], k_cliques(&adj, 2, any));
+    }
+
+    #[test]
+    fn large_sparse() {
+        let mut adj = random_graph(3000, 1, 7);
+        // Plant a clique of 40.
+        let planted: Vec<usize> = (0..40).map(|i| i * 71).collect();
+        for &a in planted.iter() {
+            for &b in planted.iter() {
+                if a != b {
+                    adj[a].push(b);
+                }
+            }
+        }
+        for to in adj.iter_mut() {
+            to.sort_unstable();
+            to.dedup();
+        }
+        assert_eq!(planted, maximum_clique(&adj));
+    }
+}
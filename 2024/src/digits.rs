@@ -0,0 +1,146 @@
+//! Exact decimal digit arithmetic on unsigned integers.  Floats run out of
+//! precision long before `u64` does: `(x as f64 + 0.1).log10().ceil()`
+//! loses its `+ 0.1` at `10^14`, and from there on every power of ten comes
+//! out a digit short.
+use crate::checked::{Checked, Overflow};
+
+macro_rules! pow10_table {
+    ($t:ty, $n:expr) => {{
+        let mut table: [$t; $n] = [1; $n];
+        let mut i = 1;
+        while i < $n {
+            table[i] = table[i - 1] * 10;
+            i += 1;
+        }
+        table
+    }};
+}
+
+/// `POW10_U64[n]` is `10^n`, for every power of ten that fits.
+pub const POW10_U64: [u64; 20] = pow10_table!(u64, 20);
+/// `POW10_U128[n]` is `10^n`, for every power of ten that fits.
+pub const POW10_U128: [u128; 39] = pow10_table!(u128, 39);
+
+pub trait Digits: Copy + Sized {
+    /// `10^n`, if that fits.
+    fn pow10(n: u32) -> Result<Self, Overflow>;
+    /// Number of decimal digits; zero has one.
+    fn digits(self) -> u32;
+    /// The two halves of a number with an even number of digits, like
+    /// 1234 into 12 and 34, or 1000 into 10 and 0.
+    fn split_half(self) -> Option<(Self, Self)>;
+    /// The digits of `self` followed by those of `rhs`, like 12 and 345
+    /// into 12345.
+    fn concat(self, rhs: Self) -> Result<Self, Overflow>;
+}
+
+macro_rules! impl_digits {
+    ($($t:ty => $table:ident),*) => {$(
+        impl Digits for $t {
+            fn pow10(n: u32) -> Result<Self, Overflow> {
+                $table.get(n as usize).copied().ok_or(Overflow)
+            }
+
+            fn digits(self) -> u32 {
+                // how many powers of ten are no bigger
+                $table.partition_point(|&p| p <= self).max(1) as u32
+            }
+
+            fn split_half(self) -> Option<(Self, Self)> {
+                let n = self.digits();
+                (n % 2 == 0).then(|| {
+                    let p = $table[n as usize / 2];
+                    (self / p, self % p)
+                })
+            }
+
+            fn concat(self, rhs: Self) -> Result<Self, Overflow> {
+                if self == 0 {
+                    return Ok(rhs);
+                }
+                self.try_mul(Self::pow10(rhs.digits())?)?.try_add(rhs)
+            }
+        }
+    )*};
+}
+
+impl_digits!(u64 => POW10_U64, u128 => POW10_U128);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every power of ten, the numbers either side of it, and both ends.
+    fn boundaries() -> Vec<u64> {
+        let mut xs = vec![0, u64::MAX - 1, u64::MAX];
+        for p in POW10_U64 {
+            xs.extend([p - 1, p, p + 1]);
+        }
+        xs.sort_unstable();
+        xs.dedup();
+        xs
+    }
+
+    #[test]
+    fn tables() {
+        for (n, p) in POW10_U64.into_iter().enumerate() {
+            assert_eq!(format!("1{}", "0".repeat(n)), p.to_string());
+            assert_eq!(u128::from(p), POW10_U128[n]);
+        }
+        assert!(u64::MAX / 10 < POW10_U64[19]);
+        assert!(u128::MAX / 10 < POW10_U128[38]);
+        assert_eq!(Err(Overflow), u64::pow10(20));
+        assert_eq!(Ok(POW10_U128[20]), u128::pow10(20));
+        assert_eq!(Err(Overflow), u128::pow10(39));
+    }
+
+    #[test]
+    fn digits() {
+        for x in (0..100_000).chain(boundaries()) {
+            let s = x.to_string();
+            assert_eq!(s.len() as u32, x.digits(), "{x}");
+            assert_eq!(s.len() as u32, u128::from(x).digits(), "{x}");
+        }
+        assert_eq!(39, u128::MAX.digits());
+        assert_eq!(38, (POW10_U128[38] - 1).digits());
+        assert_eq!(39, POW10_U128[38].digits());
+    }
+
+    #[test]
+    fn split_half() {
+        for x in (0..100_000).chain(boundaries()) {
+            let s = x.to_string();
+            let want = (s.len() % 2 == 0).then(|| {
+                let (a, b) = s.split_at(s.len() / 2);
+                (a.parse().unwrap(), b.parse().unwrap())
+            });
+            assert_eq!(want, x.split_half(), "{x}");
+            let want = want.map(|(a, b): (u64, u64)| (a.into(), b.into()));
+            assert_eq!(want, u128::from(x).split_half(), "{x}");
+        }
+        assert_eq!(None, u128::MAX.split_half());
+        let nines = POW10_U128[19] - 1;
+        assert_eq!(Some((nines, nines)), (POW10_U128[38] - 1).split_half());
+    }
+
+    #[test]
+    fn concat() {
+        let xs = boundaries();
+        for &x in xs.iter().chain(&[7, 12, 345]) {
+            for &y in xs.iter().chain(&[7, 12, 345]) {
+                let want: u128 = format!("{x}{y}").parse().unwrap_or(u128::MAX);
+                assert_eq!(
+                    u64::try_from(want).map_err(|_| Overflow),
+                    x.concat(y),
+                    "{x} {y}"
+                );
+                let wide = u128::from(x).concat(u128::from(y));
+                if want < u128::MAX {
+                    assert_eq!(Ok(want), wide, "{x} {y}");
+                }
+            }
+        }
+        assert_eq!(Ok(12345), 12_u64.concat(345));
+        assert_eq!(Err(Overflow), u128::MAX.concat(0));
+    }
+}
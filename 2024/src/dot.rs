@@ -0,0 +1,240 @@
+//! Just enough of the Graphviz DOT language to draw puzzle inputs.
+//!
+//! ```text
+//! let mut g = Graph::digraph("example");
+//! g.node_attr("shape", "circle");
+//! g.node("a").attr("color", "red");
+//! g.edge("a", "b");
+//! println!("{g}");
+//! ```
+use std::fmt::{Display, Formatter};
+
+type Attrs = Vec<(String, String)>;
+
+#[derive(Clone, Debug)]
+pub struct Graph {
+    keyword: &'static str,
+    name: String,
+    attrs: Attrs,
+    node_attrs: Attrs,
+    edge_attrs: Attrs,
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    subgraphs: Vec<Graph>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Node {
+    id: String,
+    attrs: Attrs,
+}
+
+#[derive(Clone, Debug)]
+pub struct Edge {
+    from: String,
+    to: String,
+    attrs: Attrs,
+}
+
+impl Graph {
+    fn new(keyword: &'static str, name: impl Into<String>) -> Self {
+        Self {
+            keyword,
+            name: name.into(),
+            attrs: vec![],
+            node_attrs: vec![],
+            edge_attrs: vec![],
+            nodes: vec![],
+            edges: vec![],
+            subgraphs: vec![],
+        }
+    }
+
+    /// Edges are drawn as `a -> b`.
+    pub fn digraph(name: impl Into<String>) -> Self {
+        Self::new("digraph", name)
+    }
+
+    /// Edges are drawn as `a -- b`.
+    pub fn undirected(name: impl Into<String>) -> Self {
+        Self::new("graph", name)
+    }
+
+    /// Edge style comes from whichever graph it ends up in.  Graphviz only
+    /// draws a box around those whose names start with `cluster`.
+    pub fn subgraph(name: impl Into<String>) -> Self {
+        Self::new("subgraph", name)
+    }
+
+    pub fn attr(&mut self, key: &str, value: impl Display) -> &mut Self {
+        self.attrs.push((key.to_owned(), value.to_string()));
+        self
+    }
+
+    /// Default for every node declared in this graph and its subgraphs.
+    pub fn node_attr(&mut self, key: &str, value: impl Display) -> &mut Self {
+        self.node_attrs.push((key.to_owned(), value.to_string()));
+        self
+    }
+
+    /// Default for every edge declared in this graph and its subgraphs.
+    pub fn edge_attr(&mut self, key: &str, value: impl Display) -> &mut Self {
+        self.edge_attrs.push((key.to_owned(), value.to_string()));
+        self
+    }
+
+    pub fn node(&mut self, id: impl Display) -> &mut Node {
+        self.nodes.push(Node {
+            id: id.to_string(),
+            attrs: vec![],
+        });
+        self.nodes.last_mut().expect("just pushed")
+    }
+
+    pub fn edge(&mut self, from: impl Display, to: impl Display) -> &mut Edge {
+        self.edges.push(Edge {
+            from: from.to_string(),
+            to: to.to_string(),
+            attrs: vec![],
+        });
+        self.edges.last_mut().expect("just pushed")
+    }
+
+    pub fn add_subgraph(&mut self, subgraph: Graph) -> &mut Self {
+        self.subgraphs.push(subgraph);
+        self
+    }
+
+    fn write(&self, f: &mut Formatter<'_>, depth: usize, edge_op: &str) -> std::fmt::Result {
+        let indent = "  ".repeat(depth);
+        writeln!(f, "{}{} {} {{", indent, self.keyword, Id(&self.name))?;
+        for (key, value) in self.attrs.iter() {
+            writeln!(f, "{indent}  {}={};", Id(key), Id(value))?;
+        }
+        if !self.node_attrs.is_empty() {
+            writeln!(f, "{indent}  node{};", AttrList(&self.node_attrs))?;
+        }
+        if !self.edge_attrs.is_empty() {
+            writeln!(f, "{indent}  edge{};", AttrList(&self.edge_attrs))?;
+        }
+        for subgraph in self.subgraphs.iter() {
+            subgraph.write(f, depth + 1, edge_op)?;
+        }
+        for Node { id, attrs } in self.nodes.iter() {
+            writeln!(f, "{indent}  {}{};", Id(id), AttrList(attrs))?;
+        }
+        for Edge { from, to, attrs } in self.edges.iter() {
+            writeln!(
+                f,
+                "{indent}  {} {edge_op} {}{};",
+                Id(from),
+                Id(to),
+                AttrList(attrs)
+            )?;
+        }
+        writeln!(f, "{indent}}}")
+    }
+}
+
+impl Node {
+    pub fn attr(&mut self, key: &str, value: impl Display) -> &mut Self {
+        self.attrs.push((key.to_owned(), value.to_string()));
+        self
+    }
+}
+
+impl Edge {
+    pub fn attr(&mut self, key: &str, value: impl Display) -> &mut Self {
+        self.attrs.push((key.to_owned(), value.to_string()));
+        self
+    }
+}
+
+impl Display for Graph {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let edge_op = if self.keyword == "digraph" {
+            "->"
+        } else {
+            "--"
+        };
+        self.write(f, 0, edge_op)
+    }
+}
+
+/// Bare if DOT allows it, otherwise quoted.  Text is taken literally, so a
+/// newline becomes `\n` and a backslash does not start an escape.
+struct Id<'a>(&'a str);
+
+impl Display for Id<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = self.0;
+        let is_bare_word = s
+            .bytes()
+            .next()
+            .is_some_and(|b| b.is_ascii_alphabetic() || b == b'_')
+            && s.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_');
+        let is_number = !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        let is_keyword = ["node", "edge", "graph", "digraph", "subgraph", "strict"]
+            .iter()
+            .any(|k| k.eq_ignore_ascii_case(s));
+        if (is_bare_word || is_number) && !is_keyword {
+            return f.write_str(s);
+        }
+        f.write_str("\"")?;
+        for c in s.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                _ => write!(f, "{c}")?,
+            }
+        }
+        f.write_str("\"")
+    }
+}
+
+struct AttrList<'a>(&'a Attrs);
+
+impl Display for AttrList<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        f.write_str(" [")?;
+        for (i, (key, value)) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}={}", Id(key), Id(value))?;
+        }
+        f.write_str("]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaping() {
+        assert_eq!("abc_1", Id("abc_1").to_string());
+        assert_eq!("42", Id("42").to_string());
+        assert_eq!("\"1a\"", Id("1a").to_string());
+        assert_eq!("\"node\"", Id("node").to_string());
+        assert_eq!("\"a-b\"", Id("a-b").to_string());
+        assert_eq!(r#""say \"hi\"\\\n""#, Id("say \"hi\"\\\n").to_string());
+    }
+
+    #[test]
+    fn nested() {
+        let mut g = Graph::digraph("g");
+        g.attr("rankdir", "LR");
+        let mut sub = Graph::subgraph("cluster_0");
+        sub.node_attr("color", "red");
+        sub.node("a");
+        g.add_subgraph(sub);
+        g.edge("a", "b").attr("label", "a to b");
+        let want = "digraph g {\n  rankdir=LR;\n  subgraph cluster_0 {\n    node [color=red];\n    a;\n  }\n  a -> b [label=\"a to b\"];\n}\n";
+        assert_eq!(want, g.to_string());
+    }
+}
@@ -0,0 +1,7 @@
+//! Bits shared between days; each puzzle still lives in its own `src/bin/aocNN.rs`.
+pub mod checked;
+pub mod clique;
+pub mod digits;
+pub mod dot;
+pub mod rng;
+pub mod search;
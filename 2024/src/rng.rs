@@ -0,0 +1,32 @@
+//! Seeded xorshift64, good enough for random test instances and the odd
+//! randomized check; not worth a dependency.  A plain module rather than
+//! `#[cfg(test)]`, since the bins' tests link against the non-test library.
+use std::ops::Range;
+
+pub struct XorShift64(u64);
+
+impl XorShift64 {
+    /// Any seed but zero, which is a fixed point.
+    pub fn new(seed: u64) -> Self {
+        assert_ne!(0, seed, "xorshift never leaves zero");
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// In `0..n`, slightly biased towards the low end.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// In `range`, which must not be empty.
+    pub fn range(&mut self, range: Range<i64>) -> i64 {
+        let width = range.end.abs_diff(range.start);
+        range.start.wrapping_add_unsigned(self.next_u64() % width)
+    }
+}
@@ -0,0 +1,570 @@
+//! Dijkstra's algorithm over any state space, or A* when given a heuristic.
+//!
+//! States only need to be hashable; costs anything that adds up and
+//! compares, like `u32`.  Successor functions return `(next_state, step_cost)`.
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    hash::Hash,
+    ops::Add,
+};
+
+pub trait Cost: Copy + Ord + Add<Output = Self> + Default {}
+
+impl<T: Copy + Ord + Add<Output = T> + Default> Cost for T {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path<S, C> {
+    pub cost: C,
+    /// Both ends included.
+    pub states: Vec<S>,
+}
+
+/// Cheapest path from `start` to whichever goal state is closest.
+pub fn dijkstra<S, C, I>(
+    start: S,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S, C>>
+where
+    S: Clone + Eq + Hash,
+    C: Cost,
+    I: IntoIterator<Item = (S, C)>,
+{
+    Search::new([start]).run(successors, |_| C::default(), is_goal)
+}
+
+/// Same as [`dijkstra`], but explores states in order of cost so far plus
+/// `heuristic`, which must be consistent: zero at goals, and never more than
+/// a step's cost plus the heuristic where it leads.  Merely never
+/// overestimating is not enough, since settled states are never reopened,
+/// so a cheaper way to one found later would be ignored.
+pub fn astar<S, C, I>(
+    start: S,
+    successors: impl FnMut(&S) -> I,
+    heuristic: impl FnMut(&S) -> C,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S, C>>
+where
+    S: Clone + Eq + Hash,
+    C: Cost,
+    I: IntoIterator<Item = (S, C)>,
+{
+    Search::new([start]).run(successors, heuristic, is_goal)
+}
+
+/// Cost of the cheapest path to every reachable state, from whichever of
+/// `starts` is closest.
+pub fn distances<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    successors: impl FnMut(&S) -> I,
+) -> HashMap<S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Cost,
+    I: IntoIterator<Item = (S, C)>,
+{
+    let mut search = Search::new(starts);
+    let _never = search.run(successors, |_| C::default(), |_| false);
+    search.into_distances()
+}
+
+/// The `k` cheapest paths to a goal that never visit a state twice, cheapest
+/// first, by Yen's algorithm.  Each is found by a fresh search from some
+/// state along an earlier path, so a good `heuristic` helps a lot.  It
+/// must be consistent, as for [`astar`]; it then stays so on the smaller
+/// graphs those searches see, which only lack some steps.
+pub fn k_cheapest_paths<S, C, I>(
+    start: S,
+    k: usize,
+    mut successors: impl FnMut(&S) -> I,
+    mut heuristic: impl FnMut(&S) -> C,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Vec<Path<S, C>>
+where
+    S: Clone + Eq + Hash,
+    C: Cost,
+    I: IntoIterator<Item = (S, C)>,
+{
+    let mut found = vec![];
+    if k == 0 {
+        return found;
+    }
+    let Some(first) = Search::new([start]).run(&mut successors, &mut heuristic, &mut is_goal)
+    else {
+        return found;
+    };
+    found.push(first);
+    let mut candidates: Vec<Path<S, C>> = vec![];
+    while found.len() < k {
+        let last = found.last().expect("at least one").states.clone();
+        let mut root_cost = C::default();
+        for i in 0..last.len() - 1 {
+            let (root, spur) = (&last[..i], &last[i]);
+            // Steps already taken by a path sharing this root must not be
+            // retaken, and the root itself is off limits.
+            let taken: HashSet<&S> = found
+                .iter()
+                .filter(|p| p.states.len() > i + 1 && p.states[..=i] == last[..=i])
+                .map(|p| &p.states[i + 1])
+                .collect();
+            let visited: HashSet<&S> = root.iter().collect();
+            let spur_path = Search::new([spur.clone()]).run(
+                |s| {
+                    let at_spur = s == spur;
+                    successors(s)
+                        .into_iter()
+                        .filter(|(next, _)| {
+                            !(visited.contains(next) || at_spur && taken.contains(next))
+                        })
+                        .collect::<Vec<_>>()
+                },
+                &mut heuristic,
+                &mut is_goal,
+            );
+            if let Some(spur_path) = spur_path {
+                let mut states = root.to_vec();
+                states.extend(spur_path.states);
+                let candidate = Path {
+                    cost: root_cost + spur_path.cost,
+                    states,
+                };
+                if !candidates.contains(&candidate) && !found.contains(&candidate) {
+                    candidates.push(candidate);
+                }
+            }
+            root_cost = root_cost
+                + successors(spur)
+                    .into_iter()
+                    .filter(|(next, _)| *next == last[i + 1])
+                    .map(|(_, cost)| cost)
+                    .min()
+                    .expect("consecutive states on a path");
+        }
+        let Some(cheapest) = (0..candidates.len()).min_by_key(|&i| candidates[i].cost) else {
+            break;
+        };
+        found.push(candidates.swap_remove(cheapest));
+    }
+    found
+}
+
+/// The search itself, for when the convenience functions above are not
+/// enough: several starts, resuming after a goal, both a path and the
+/// distance map from one run, or every cheapest path rather than just one.
+pub struct Search<S, C> {
+    ids: HashMap<S, usize>,
+    states: Vec<S>,
+    best: Vec<Option<C>>,
+    /// Every neighbor that reaches the state at its best cost so far.
+    parents: Vec<Vec<usize>>,
+    settled: Vec<bool>,
+    /// Ids in the order they were settled, which is a topological order of
+    /// the predecessor links.
+    order: Vec<usize>,
+    /// `(cost + heuristic, cost, id)`
+    heap: BinaryHeap<Reverse<(C, C, usize)>>,
+    /// The goal that [`Self::run`] last stopped at, whose successors it has
+    /// yet to push.
+    unexpanded: Option<usize>,
+}
+
+impl<S, C> Search<S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Cost,
+{
+    pub fn new(starts: impl IntoIterator<Item = S>) -> Self {
+        let mut search = Self {
+            ids: HashMap::new(),
+            states: vec![],
+            best: vec![],
+            parents: vec![],
+            settled: vec![],
+            order: vec![],
+            heap: BinaryHeap::new(),
+            unexpanded: None,
+        };
+        for start in starts {
+            let id = search.intern(start);
+            search.best[id] = Some(C::default());
+            search.heap.push(Reverse((C::default(), C::default(), id)));
+        }
+        search
+    }
+
+    fn intern(&mut self, state: S) -> usize {
+        if let Some(&id) = self.ids.get(&state) {
+            return id;
+        }
+        let id = self.states.len();
+        self.ids.insert(state.clone(), id);
+        self.states.push(state);
+        self.best.push(None);
+        self.parents.push(vec![]);
+        self.settled.push(false);
+        id
+    }
+
+    /// Settles states until one satisfies `is_goal`, or there are none left.
+    /// Calling it again carries on from where it stopped, starting with the
+    /// successors of the goal it returned.  `heuristic` must be consistent,
+    /// as for [`astar`].
+    pub fn run<I>(
+        &mut self,
+        mut successors: impl FnMut(&S) -> I,
+        mut heuristic: impl FnMut(&S) -> C,
+        mut is_goal: impl FnMut(&S) -> bool,
+    ) -> Option<Path<S, C>>
+    where
+        I: IntoIterator<Item = (S, C)>,
+    {
+        if let Some(id) = self.unexpanded.take() {
+            self.expand(id, &mut successors, &mut heuristic);
+        }
+        while let Some(Reverse((_estimate, cost, id))) = self.heap.pop() {
+            if self.settled[id] || self.best[id].is_some_and(|best| best < cost) {
+                // stale entry
+                continue;
+            }
+            self.settled[id] = true;
+            self.order.push(id);
+            if is_goal(&self.states[id]) {
+                self.unexpanded = Some(id);
+                return self.path_to_id(id);
+            }
+            self.expand(id, &mut successors, &mut heuristic);
+        }
+        None
+    }
+
+    /// Pushes the successors of the settled state `id`.
+    fn expand<I>(
+        &mut self,
+        id: usize,
+        successors: &mut impl FnMut(&S) -> I,
+        heuristic: &mut impl FnMut(&S) -> C,
+    ) where
+        I: IntoIterator<Item = (S, C)>,
+    {
+        let cost = self.best[id].expect("settled states have a cost");
+        let state = self.states[id].clone();
+        for (next, step) in successors(&state) {
+            let next_cost = cost + step;
+            let next_id = self.intern(next);
+            if self.settled[next_id] {
+                continue;
+            }
+            match self.best[next_id] {
+                Some(best) if best < next_cost => {}
+                Some(best) if best == next_cost => self.parents[next_id].push(id),
+                _ => {
+                    self.best[next_id] = Some(next_cost);
+                    self.parents[next_id] = vec![id];
+                    let estimate = next_cost + heuristic(&self.states[next_id]);
+                    self.heap.push(Reverse((estimate, next_cost, next_id)));
+                }
+            }
+        }
+    }
+
+    /// Only for settled states, whose costs are final.
+    pub fn distance(&self, state: &S) -> Option<C> {
+        let &id = self.ids.get(state)?;
+        self.settled[id].then(|| self.best[id]).flatten()
+    }
+
+    pub fn path_to(&self, state: &S) -> Option<Path<S, C>> {
+        let &id = self.ids.get(state)?;
+        self.settled[id].then(|| self.path_to_id(id)).flatten()
+    }
+
+    fn path_to_id(&self, id: usize) -> Option<Path<S, C>> {
+        let cost = self.best[id]?;
+        let mut states = vec![self.states[id].clone()];
+        let mut at = id;
+        while let Some(&p) = self.parents[at].first() {
+            states.push(self.states[p].clone());
+            at = p;
+        }
+        states.reverse();
+        Some(Path { cost, states })
+    }
+
+    /// Every cheapest path that ends in whichever of `goals` are cheapest to
+    /// reach.  Only settled goals count, so either run until all of them are
+    /// settled or run to exhaustion.  Step costs must be positive, otherwise
+    /// ties across zero-cost steps are missed.
+    pub fn cheapest_paths<'a>(
+        &'a self,
+        goals: impl IntoIterator<Item = &'a S>,
+    ) -> PathDag<'a, S, C> {
+        let goals: Vec<usize> = goals
+            .into_iter()
+            .filter_map(|g| self.ids.get(g).copied())
+            .filter(|&id| self.settled[id])
+            .collect();
+        let cost = goals.iter().filter_map(|&id| self.best[id]).min();
+        let ends: Vec<usize> = goals
+            .into_iter()
+            .filter(|&id| self.best[id] == cost)
+            .collect();
+        let mut on_path = vec![false; self.states.len()];
+        let mut todo = ends.clone();
+        while let Some(id) = todo.pop() {
+            if on_path[id] {
+                continue;
+            }
+            on_path[id] = true;
+            todo.extend(self.parents[id].iter().copied());
+        }
+        PathDag {
+            search: self,
+            cost,
+            ends,
+            on_path,
+        }
+    }
+
+    /// Every settled state and its cost.
+    pub fn into_distances(self) -> HashMap<S, C> {
+        let Self {
+            states,
+            best,
+            settled,
+            ..
+        } = self;
+        states
+            .into_iter()
+            .zip(best)
+            .zip(settled)
+            .filter_map(|((state, best), settled)| Some((state, best.filter(|_| settled)?)))
+            .collect()
+    }
+}
+
+/// The union of all cheapest paths to a set of goals, without ever writing
+/// any of them out in full unless asked to.
+pub struct PathDag<'a, S, C> {
+    search: &'a Search<S, C>,
+    cost: Option<C>,
+    ends: Vec<usize>,
+    on_path: Vec<bool>,
+}
+
+impl<'a, S, C> PathDag<'a, S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Cost,
+{
+    /// `None` if no goal was reached.
+    pub fn cost(&self) -> Option<C> {
+        self.cost
+    }
+
+    /// Every state on at least one cheapest path.
+    pub fn states(&self) -> impl Iterator<Item = &'a S> + '_ {
+        self.on_path
+            .iter()
+            .enumerate()
+            .filter(|&(_, &on)| on)
+            .map(|(id, _)| &self.search.states[id])
+    }
+
+    /// Saturates rather than overflowing; open areas have a great many.
+    pub fn count_paths(&self) -> u128 {
+        let mut counts = vec![0_u128; self.search.states.len()];
+        for &id in self.search.order.iter().filter(|&&id| self.on_path[id]) {
+            let parents = &self.search.parents[id];
+            counts[id] = if parents.is_empty() {
+                1
+            } else {
+                parents
+                    .iter()
+                    .fold(0_u128, |sum, &p| sum.saturating_add(counts[p]))
+            };
+        }
+        self.ends
+            .iter()
+            .fold(0, |sum, &id| sum.saturating_add(counts[id]))
+    }
+
+    /// One at a time, start first, in no particular order.
+    pub fn paths(&self) -> Paths<'_, S, C> {
+        Paths {
+            search: self.search,
+            ends: self.ends.clone(),
+            stack: vec![],
+        }
+    }
+}
+
+/// Depth-first walk backwards through the predecessor links.
+pub struct Paths<'a, S, C> {
+    search: &'a Search<S, C>,
+    ends: Vec<usize>,
+    /// `(id, index of the next parent to try)`
+    stack: Vec<(usize, usize)>,
+}
+
+impl<S: Clone, C> Iterator for Paths<'_, S, C> {
+    type Item = Vec<S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Some(&mut (id, ref mut next_parent)) = self.stack.last_mut() else {
+                self.stack.push((self.ends.pop()?, 0));
+                continue;
+            };
+            let parents = &self.search.parents[id];
+            if parents.is_empty() {
+                let path = self
+                    .stack
+                    .iter()
+                    .rev()
+                    .map(|&(id, _)| self.search.states[id].clone())
+                    .collect();
+                self.stack.pop();
+                return Some(path);
+            }
+            if let Some(&p) = parents.get(*next_parent) {
+                *next_parent += 1;
+                self.stack.push((p, 0));
+            } else {
+                self.stack.pop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift64;
+
+    #[test]
+    fn weighted() {
+        // 0 -1-> 1 -1-> 3, 0 -5-> 3, 0 -1-> 2 -1-> 3 -1-> 4
+        let edges = |&s: &u8| -> Vec<(u8, u32)> {
+            match s {
+                0 => vec![(1, 1), (3, 5), (2, 1)],
+                1 => vec![(3, 1)],
+                2 => vec![(3, 1)],
+                3 => vec![(4, 1)],
+                _ => vec![],
+            }
+        };
+        let path = dijkstra(0, edges, |&s| s == 4).unwrap();
+        assert_eq!(3, path.cost);
+        assert_eq!(4, path.states.len());
+        assert_eq!(Some(&0), path.states.first());
+        assert_eq!(Some(&4), path.states.last());
+        assert_eq!(None, dijkstra(4, edges, |&s| s == 0));
+        let all = distances([0], edges);
+        assert_eq!(5, all.len());
+        assert_eq!(Some(&2), all.get(&3));
+    }
+
+    #[test]
+    fn resume_past_goal() {
+        // 0 -1-> 1 -1-> 2, where 2 is only reachable through the first goal
+        let chain = |&s: &u8| (s < 2).then_some((s + 1, 1_u32));
+        let mut search = Search::new([0]);
+        assert_eq!(
+            Some(1),
+            search.run(chain, |_| 0, |&s| s == 1).map(|p| p.cost)
+        );
+        let path = search.run(chain, |_| 0, |&s| s == 2).unwrap();
+        assert_eq!((2, vec![0, 1, 2]), (path.cost, path.states));
+        assert!(search.run(chain, |_| 0, |_| true).is_none());
+    }
+
+    #[test]
+    fn astar_agrees_with_dijkstra() {
+        let mut rng = XorShift64::new(0x5eed);
+        let n = 40_i32;
+        for _ in 0..20 {
+            let walls: Vec<bool> = (0..n * n).map(|_| rng.below(10) < 3).collect();
+            let open = |x: i32, y: i32| {
+                (0..n).contains(&x) && (0..n).contains(&y) && !walls[(y * n + x) as usize]
+            };
+            let successors = |&(x, y): &(i32, i32)| {
+                [(0, 1), (1, 0), (0, -1), (-1, 0)]
+                    .into_iter()
+                    .map(move |(dx, dy)| (x + dx, y + dy))
+                    .filter(|&(xx, yy)| open(xx, yy))
+                    .map(|xy| (xy, 1_u32))
+            };
+            let goal = (n - 1, n - 1);
+            let plain = dijkstra((0, 0), successors, |&xy| xy == goal);
+            let guided = astar(
+                (0, 0),
+                successors,
+                |&(x, y)| (goal.0 - x + goal.1 - y) as u32,
+                |&xy| xy == goal,
+            );
+            assert_eq!(
+                plain.as_ref().map(|p| p.cost),
+                guided.as_ref().map(|p| p.cost)
+            );
+            if let Some(path) = guided {
+                assert_eq!(path.cost as usize + 1, path.states.len());
+                for w in path.states.windows(2) {
+                    assert_eq!(1, (w[0].0 - w[1].0).abs() + (w[0].1 - w[1].1).abs());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn every_cheapest_path() {
+        // 3x3 open grid, corner to corner: C(4, 2) paths through all 9 tiles.
+        let successors = |&(x, y): &(u8, u8)| {
+            [(x + 1, y), (x, y + 1)]
+                .into_iter()
+                .filter(|&(xx, yy)| xx < 3 && yy < 3)
+                .map(|xy| (xy, 1_u32))
+        };
+        let mut search = Search::new([(0, 0)]);
+        let _never = search.run(successors, |_| 0, |_| false);
+        let dag = search.cheapest_paths([&(2, 2)]);
+        assert_eq!(Some(4), dag.cost());
+        assert_eq!(6, dag.count_paths());
+        assert_eq!(9, dag.states().count());
+        let mut paths: Vec<_> = dag.paths().collect();
+        paths.sort();
+        paths.dedup();
+        assert_eq!(6, paths.len());
+        assert!(paths.iter().all(|p| p.len() == 5 && p[0] == (0, 0)));
+        let dag = search.cheapest_paths([&(1, 0)]);
+        assert_eq!(1, dag.count_paths());
+        assert_eq!(vec![vec![(0, 0), (1, 0)]], dag.paths().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn k_cheapest() {
+        // 3x3 open grid again, plus a pricey shortcut straight to the goal.
+        let successors = |&(x, y): &(u8, u8)| {
+            let mut next: Vec<((u8, u8), u32)> = [(x + 1, y), (x, y + 1)]
+                .into_iter()
+                .filter(|&(xx, yy)| xx < 3 && yy < 3)
+                .map(|xy| (xy, 1))
+                .collect();
+            if (x, y) == (0, 0) {
+                next.push(((2, 2), 5));
+            }
+            next
+        };
+        let paths = k_cheapest_paths((0, 0), 10, successors, |_| 0, |&xy| xy == (2, 2));
+        let costs: Vec<u32> = paths.iter().map(|p| p.cost).collect();
+        assert_eq!(vec![4, 4, 4, 4, 4, 4, 5], costs);
+        let mut distinct: Vec<_> = paths.iter().map(|p| &p.states).collect();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(7, distinct.len());
+        assert_eq!(
+            3,
+            k_cheapest_paths((0, 0), 3, successors, |_| 0, |&xy| xy == (2, 2)).len()
+        );
+    }
+}